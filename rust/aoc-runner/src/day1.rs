@@ -0,0 +1,32 @@
+use day1::{sum_calibrations, CalibrationMode, DigitMatcher, ErrorPolicy};
+
+use crate::{BoxError, Solution};
+
+/// Trebuchet?!: sum the calibration values made of the first and last digit of every line
+#[derive(Debug, Default)]
+pub struct Day1 {
+    matcher: DigitMatcher,
+}
+
+impl Day1 {
+    fn sum(&self, input: &str, mode: CalibrationMode) -> Result<String, BoxError> {
+        let summary = sum_calibrations(&self.matcher, input.as_bytes(), mode, ErrorPolicy::Strict)?;
+        Ok(summary.sum.to_string())
+    }
+}
+
+impl Solution for Day1 {
+    type Input = String;
+
+    fn parse(&self, input: &str) -> Result<String, BoxError> {
+        Ok(String::from(input))
+    }
+
+    fn part1(&self, input: &String) -> Result<String, BoxError> {
+        self.sum(input, CalibrationMode::Digits)
+    }
+
+    fn part2(&self, input: &String) -> Result<String, BoxError> {
+        self.sum(input, CalibrationMode::DigitsAndWords)
+    }
+}
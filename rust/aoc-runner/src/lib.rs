@@ -0,0 +1,104 @@
+//! A shared runner for the Advent of Code solutions in this workspace. Every day implements
+//! [`Solution`] and is registered in [`registry`], which is what the `aoc` binary runs.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+mod day1;
+
+pub use day1::Day1;
+
+/// The error type of the runner and the solutions it runs
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// A solution to one day of the puzzles
+pub trait Solution {
+    /// The puzzle input parsed into whatever form both parts work from
+    type Input;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, BoxError>;
+    fn part1(&self, input: &Self::Input) -> Result<String, BoxError>;
+    fn part2(&self, input: &Self::Input) -> Result<String, BoxError>;
+}
+
+/// The answers to both parts of a day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answers {
+    pub part1: String,
+    pub part2: String,
+}
+
+/// A [`Solution`] with its input type erased, so that solutions to different days can be kept
+/// side by side in a [`Registry`]
+pub trait Runner {
+    fn run(&self, input: &str) -> Result<Answers, BoxError>;
+}
+
+impl<S: Solution> Runner for S {
+    fn run(&self, input: &str) -> Result<Answers, BoxError> {
+        let input = self.parse(input)?;
+        Ok(Answers {
+            part1: self.part1(&input)?,
+            part2: self.part2(&input)?,
+        })
+    }
+}
+
+/// The solutions by day
+#[derive(Default)]
+pub struct Registry {
+    days: BTreeMap<u32, Box<dyn Runner>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Add the solution for a day, replacing any earlier one
+    pub fn register<S: Solution + 'static>(&mut self, day: u32, solution: S) {
+        self.days.insert(day, Box::new(solution));
+    }
+
+    pub fn get(&self, day: u32) -> Option<&dyn Runner> {
+        self.days.get(&day).map(|runner| runner.as_ref())
+    }
+
+    /// Every registered day in order, with its solution
+    pub fn days(&self) -> impl Iterator<Item = (u32, &dyn Runner)> {
+        self.days
+            .iter()
+            .map(|(&day, runner)| (day, runner.as_ref()))
+    }
+}
+
+/// All the days solved so far
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.register(1, Day1::default());
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let registry = registry();
+        assert_eq!(
+            registry.days().map(|(day, _)| day).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert!(registry.get(2).is_none());
+
+        let answers = registry.get(1).unwrap().run("two1nine\n1abc2\n").unwrap();
+        assert_eq!(
+            answers,
+            Answers {
+                part1: String::from("23"),
+                part2: String::from("41"),
+            }
+        );
+    }
+}
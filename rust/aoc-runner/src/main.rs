@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use aoc_runner::{registry, Runner};
+
+const USAGE: &str = "usage: aoc [--input-dir DIR] [--input FILE] DAY|all
+
+Runs the solution to a day, or to every day solved so far, and prints both answers
+
+options:
+    --input-dir DIR  read the input of day N from DIR/dayN/input.txt (default .)
+    --input FILE     read the input from FILE instead, only when running a single day
+    -h, --help       print this message";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Days {
+    One(u32),
+    All,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    days: Days,
+    input_dir: PathBuf,
+    input: Option<PathBuf>,
+}
+
+// Parse the command line, not including the program name. Ok(None) means help was asked for
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Args>, String> {
+    let mut days = None;
+    let mut input_dir = PathBuf::from(".");
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input-dir" => input_dir = args.next().ok_or("--input-dir needs a value")?.into(),
+            "--input" => input = Some(args.next().ok_or("--input needs a value")?.into()),
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg:?}")),
+            _ if days.is_some() => return Err(format!("unexpected argument {arg:?}")),
+            "all" => days = Some(Days::All),
+            _ => match arg.parse() {
+                Ok(day) => days = Some(Days::One(day)),
+                Err(_) => return Err(format!("invalid day {arg:?}, expected a number or all")),
+            },
+        }
+    }
+
+    let days = days.ok_or("which day to run is missing")?;
+    if input.is_some() && days == Days::All {
+        return Err(String::from(
+            "--input can only be used when running a single day",
+        ));
+    }
+    Ok(Some(Args {
+        days,
+        input_dir,
+        input,
+    }))
+}
+
+// Read the input and print the answers for a single day, or what went wrong
+fn run_day(day: u32, runner: &dyn Runner, input: &Path) -> bool {
+    println!("day {day}");
+    let result = std::fs::read_to_string(input)
+        .map_err(|err| format!("failed to read {}: {err}", input.display()))
+        .and_then(|input| runner.run(&input).map_err(|err| err.to_string()));
+    match result {
+        Ok(answers) => {
+            println!("  part 1: {}", answers.part1);
+            println!("  part 2: {}", answers.part2);
+            true
+        }
+        Err(err) => {
+            eprintln!("aoc: day {day}: {err}");
+            false
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("aoc: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let registry = registry();
+    let days: Vec<(u32, &dyn Runner)> = match args.days {
+        Days::All => registry.days().collect(),
+        Days::One(day) => match registry.get(day) {
+            Some(runner) => vec![(day, runner)],
+            None => {
+                eprintln!("aoc: day {day} has not been solved yet");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let mut ok = true;
+    for (day, runner) in days {
+        let input = match &args.input {
+            Some(input) => input.clone(),
+            None => args.input_dir.join(format!("day{day}")).join("input.txt"),
+        };
+        ok &= run_day(day, runner, &input);
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse(&["1"]),
+            Ok(Some(Args {
+                days: Days::One(1),
+                input_dir: PathBuf::from("."),
+                input: None,
+            }))
+        );
+        assert_eq!(
+            parse(&["--input-dir", "inputs", "all"]),
+            Ok(Some(Args {
+                days: Days::All,
+                input_dir: PathBuf::from("inputs"),
+                input: None,
+            }))
+        );
+        assert_eq!(
+            parse(&["--input", "day1.txt", "1"]).map(|args| args.unwrap().input),
+            Ok(Some(PathBuf::from("day1.txt")))
+        );
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["one"]).is_err());
+        assert!(parse(&["1", "2"]).is_err());
+        assert!(parse(&["--input", "day1.txt", "all"]).is_err());
+    }
+}
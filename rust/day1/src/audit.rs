@@ -0,0 +1,138 @@
+use crate::automaton::Match;
+use crate::calibration::{calibrate, CalibrationError, CalibrationMode, DigitMatcher};
+
+/// What could make the calibration of a line come out wrong, see [`DigitMatcher::audit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineAudit {
+    /// Pairs of digits whose words overlap, like the "eight" and "two" of "eightwo"
+    pub overlaps: Vec<(Match<u32>, Match<u32>)>,
+    pub part1: Result<u32, CalibrationError>,
+    pub part2: Result<u32, CalibrationError>,
+    /// The part 2 value if each digit had to end before the next one could start, the way a
+    /// left to right search and replace of the words reads the line
+    pub without_overlaps: Result<u32, CalibrationError>,
+}
+
+impl LineAudit {
+    pub fn has_overlaps(&self) -> bool {
+        !self.overlaps.is_empty()
+    }
+
+    pub fn parts_differ(&self) -> bool {
+        self.part1 != self.part2
+    }
+
+    pub fn overlap_changes_result(&self) -> bool {
+        self.part2 != self.without_overlaps
+    }
+
+    /// Whether the line falls in any of the categories
+    pub fn is_ambiguous(&self) -> bool {
+        self.has_overlaps() || self.parts_differ() || self.overlap_changes_result()
+    }
+}
+
+impl DigitMatcher {
+    /// Check a line for overlapping digit words, for a different result in part 1 and part 2,
+    /// and for a result that depends on letting digits overlap
+    pub fn audit(&self, line: &str) -> LineAudit {
+        let digits = self.digits(line, CalibrationMode::DigitsAndWords);
+        let overlaps = digits
+            .windows(2)
+            .filter(|pair| pair[1].start < pair[0].start + pair[0].len)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+
+        let without_overlaps = if line.is_empty() {
+            Err(CalibrationError::EmptyLine)
+        } else {
+            let mut end = 0;
+            let separate = digits.iter().filter(|m| {
+                let keep = m.start >= end;
+                if keep {
+                    end = m.start + m.len;
+                }
+                keep
+            });
+            self.reducer().reduce(separate.map(|m| m.value))
+        };
+
+        LineAudit {
+            overlaps,
+            part1: calibrate(self, line, CalibrationMode::Digits),
+            part2: calibrate(self, line, CalibrationMode::DigitsAndWords),
+            without_overlaps,
+        }
+    }
+}
+
+/// The number of lines audited and how many fall in each category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AuditSummary {
+    pub lines: usize,
+    pub overlapping: usize,
+    pub parts_differ: usize,
+    pub overlap_changes_result: usize,
+}
+
+impl AuditSummary {
+    pub fn add(&mut self, audit: &LineAudit) {
+        self.lines += 1;
+        self.overlapping += audit.has_overlaps() as usize;
+        self.parts_differ += audit.parts_differ() as usize;
+        self.overlap_changes_result += audit.overlap_changes_result() as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit() {
+        let matcher = DigitMatcher::default();
+        let audit = matcher.audit("eightwothree");
+        assert_eq!(audit.overlaps.len(), 1);
+        assert_eq!(
+            (audit.overlaps[0].0.value, audit.overlaps[0].1.value),
+            (8, 2)
+        );
+        assert_eq!(audit.part1, Err(CalibrationError::NoDigit));
+        assert_eq!(audit.part2, Ok(83));
+        assert_eq!(audit.without_overlaps, Ok(83));
+        assert!(audit.has_overlaps() && audit.parts_differ() && !audit.overlap_changes_result());
+
+        // Only the overlap at the end changes the last digit
+        let audit = matcher.audit("1eightwo");
+        assert_eq!((&audit.part2, &audit.without_overlaps), (&Ok(12), &Ok(18)));
+        assert!(audit.overlap_changes_result());
+
+        let audit = matcher.audit("xtwone3four");
+        assert_eq!(audit.overlaps.len(), 1);
+        assert_eq!((&audit.part1, &audit.part2), (&Ok(33), &Ok(24)));
+        assert!(!audit.overlap_changes_result());
+
+        let audit = matcher.audit("zoneight234");
+        assert_eq!((&audit.part1, &audit.part2), (&Ok(24), &Ok(14)));
+        assert!(audit.has_overlaps() && !audit.overlap_changes_result());
+
+        let audit = matcher.audit("a1b2c3");
+        assert!(!audit.is_ambiguous());
+        assert!(!matcher.audit("").is_ambiguous());
+        assert!(!matcher.audit("xyz").is_ambiguous());
+
+        let mut summary = AuditSummary::default();
+        for line in ["eightwothree", "1eightwo", "a1b2c3", "two"] {
+            summary.add(&matcher.audit(line));
+        }
+        assert_eq!(
+            summary,
+            AuditSummary {
+                lines: 4,
+                overlapping: 2,
+                parts_differ: 3,
+                overlap_changes_result: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::trie::Trie;
+
+// A node of the Aho-Corasick automaton. Nodes live in a flat vector and refer to each other by
+// index, with the root at index 0
+#[derive(Debug)]
+pub(crate) struct AcNode<K, V> {
+    pub(crate) next: HashMap<K, usize>,
+    // The node for the longest proper suffix of this node's key that is also a prefix of some key
+    pub(crate) fail: usize,
+    // The nearest node along the failure chain holding a value, so that we can report keys that
+    // end inside a longer partial match
+    pub(crate) output: Option<usize>,
+    pub(crate) val: Option<V>,
+    // The length of the key spelled by the path from the root to this node
+    pub(crate) depth: usize,
+}
+
+/// An Aho-Corasick automaton over the keys of a trie, finding every key occurring in a sequence of
+/// symbols in a single pass without ever backtracking
+#[derive(Debug)]
+pub struct AhoCorasick<K, V> {
+    pub(crate) nodes: Vec<AcNode<K, V>>,
+    // The length of the longest key, which bounds how far back a match can start
+    pub(crate) max_depth: usize,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> AhoCorasick<K, V> {
+    pub fn new(trie: &Trie<K, V>) -> AhoCorasick<K, V> {
+        // Copy the trie into the flat vector breadth first, so that every node comes after its
+        // parent and after all nodes with shorter keys. A value under the empty key is left out,
+        // as it would be found everywhere without reading a single symbol
+        let mut nodes = vec![AcNode {
+            next: HashMap::new(),
+            fail: 0,
+            output: None,
+            val: None,
+            depth: 0,
+        }];
+        let mut queue = VecDeque::from([(trie, 0)]);
+        while let Some((trie_node, idx)) = queue.pop_front() {
+            for (sym, child) in &trie_node.next {
+                let child_idx = nodes.len();
+                nodes.push(AcNode {
+                    next: HashMap::new(),
+                    fail: 0,
+                    output: None,
+                    val: child.val.clone(),
+                    depth: nodes[idx].depth + 1,
+                });
+                nodes[idx].next.insert(sym.clone(), child_idx);
+                queue.push_back((child, child_idx));
+            }
+        }
+
+        // Now compute the failure and output links in the same breadth first order, which
+        // guarantees the links of shorter keys are already known when we need them
+        for idx in 0..nodes.len() {
+            let children: Vec<(K, usize)> = nodes[idx]
+                .next
+                .iter()
+                .map(|(sym, &child)| (sym.clone(), child))
+                .collect();
+            for (sym, child) in children {
+                let fail = if idx == 0 {
+                    0
+                } else {
+                    Self::goto(&nodes, nodes[idx].fail, &sym)
+                };
+                nodes[child].fail = fail;
+                nodes[child].output = if nodes[fail].val.is_some() {
+                    Some(fail)
+                } else {
+                    nodes[fail].output
+                };
+            }
+        }
+
+        let max_depth = nodes.iter().map(|node| node.depth).max().unwrap_or(0);
+        AhoCorasick { nodes, max_depth }
+    }
+
+    // The values held by the nodes on the path spelling key from the root, one per symbol,
+    // stopping early if the key leaves the trie
+    pub(crate) fn walk<I: IntoIterator<Item = K>>(&self, key: I) -> Vec<Option<V>> {
+        let mut state = 0;
+        let mut path = Vec::new();
+        for sym in key {
+            match self.nodes[state].next.get(&sym) {
+                Some(&next) => state = next,
+                None => break,
+            }
+            path.push(self.nodes[state].val.clone());
+        }
+        path
+    }
+
+    // Follow the edge for sym from state, falling back along the failure links until some node has
+    // one. The root absorbs every symbol it has no edge for
+    pub(crate) fn goto(nodes: &[AcNode<K, V>], mut state: usize, sym: &K) -> usize {
+        loop {
+            if let Some(&next) = nodes[state].next.get(sym) {
+                break next;
+            }
+            if state == 0 {
+                break 0;
+            }
+            state = nodes[state].fail;
+        }
+    }
+
+    // The first node at or below state in the output chain that holds a value
+    pub(crate) fn first_output(&self, state: usize) -> Option<usize> {
+        if self.nodes[state].val.is_some() {
+            Some(state)
+        } else {
+            self.nodes[state].output
+        }
+    }
+}
+
+/// A key found in a line, with the byte offset at which it starts and its length in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<V> {
+    pub value: V,
+    pub start: usize,
+    pub len: usize,
+}
+
+impl<V: Clone> AhoCorasick<char, V> {
+    /// Iterate over every key found in the line, including ones overlapping each other like the
+    /// "eight" and "two" in "eightwo". Matches come in order of where they end, and matches ending
+    /// at the same position come longest first
+    pub fn matches<'a>(&'a self, line: &'a str) -> Matches<'a, V> {
+        Matches {
+            matcher: self,
+            chars: line.char_indices(),
+            state: 0,
+            starts: VecDeque::with_capacity(self.max_depth),
+            end: 0,
+            out: None,
+        }
+    }
+}
+
+/// Iterator over the matches in a line, see [`AhoCorasick::matches`]
+pub struct Matches<'a, V> {
+    matcher: &'a AhoCorasick<char, V>,
+    chars: std::str::CharIndices<'a>,
+    state: usize,
+    // Byte offsets of the most recent characters, enough of them to find the start of the longest
+    // key ending at the current character
+    starts: VecDeque<usize>,
+    // Byte offset just past the current character
+    end: usize,
+    // The next node in the output chain of the current state that we have yet to report
+    out: Option<usize>,
+}
+
+impl<V: Clone> Iterator for Matches<'_, V> {
+    type Item = Match<V>;
+
+    fn next(&mut self) -> Option<Match<V>> {
+        loop {
+            if let Some(idx) = self.out {
+                let node = &self.matcher.nodes[idx];
+                self.out = node.output;
+                let start = self.starts[self.starts.len() - node.depth];
+                return Some(Match {
+                    value: node.val.clone().expect("output nodes always hold a value"),
+                    start,
+                    len: self.end - start,
+                });
+            }
+
+            let (offset, ch) = self.chars.next()?;
+            if self.starts.len() == self.matcher.max_depth {
+                self.starts.pop_front();
+            }
+            self.starts.push_back(offset);
+            self.end = offset + ch.len_utf8();
+            self.state = AhoCorasick::goto(&self.matcher.nodes, self.state, &ch);
+            self.out = self.matcher.first_output(self.state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aho_corasick() {
+        let mut trie = Trie::new();
+        for (key, val) in [("he", 1), ("she", 2), ("his", 3), ("hers", 4)] {
+            trie.insert(key.chars(), val);
+        }
+        let matcher = AhoCorasick::new(&trie);
+
+        let found: Vec<u32> = matcher.matches("ushers").map(|m| m.value).collect();
+        assert_eq!(found, vec![2, 1, 4]);
+        assert_eq!(matcher.walk("hers".chars()), [None, Some(1), None, Some(4)]);
+        assert_eq!(matcher.walk("hx".chars()), [None]);
+
+        trie.insert("".chars(), 0);
+        let matcher = AhoCorasick::new(&trie);
+        let found: Vec<u32> = matcher.matches("xhe").map(|m| m.value).collect();
+        assert_eq!(found, vec![1]);
+
+        let matcher = AhoCorasick::new(&Trie::with_digits());
+        let found: Vec<u32> = matcher.matches("xtwoneighthree").map(|m| m.value).collect();
+        assert_eq!(found, vec![2, 1, 8, 3]);
+    }
+
+    #[test]
+    fn test_matches() {
+        let matcher = AhoCorasick::new(&Trie::with_digits());
+        let found: Vec<(u32, usize, usize)> = matcher
+            .matches("eightwothree")
+            .map(|m| (m.value, m.start, m.len))
+            .collect();
+        assert_eq!(found, vec![(8, 0, 5), (2, 4, 3), (3, 7, 5)]);
+
+        let found: Vec<(u32, usize, usize)> = matcher
+            .matches("7twone")
+            .map(|m| (m.value, m.start, m.len))
+            .collect();
+        assert_eq!(found, vec![(7, 0, 1), (2, 1, 3), (1, 3, 3)]);
+
+        // Offsets are in bytes, so multi-byte characters before a match shift it accordingly
+        let found: Vec<Match<u32>> = matcher.matches("äsix4").collect();
+        assert_eq!(
+            found,
+            vec![
+                Match {
+                    value: 6,
+                    start: 2,
+                    len: 3
+                },
+                Match {
+                    value: 4,
+                    start: 5,
+                    len: 1
+                },
+            ]
+        );
+
+        assert_eq!(matcher.matches("hwqesaasd").next(), None);
+    }
+}
@@ -0,0 +1,1086 @@
+use crate::automaton::{AhoCorasick, Match};
+use crate::dense::DenseAutomaton;
+use crate::frozen::FrozenTrie;
+use crate::normalize::{FoldedLine, MatchOptions};
+use crate::numbers::NumberParser;
+use crate::reduce::Reducer;
+use crate::trie::{MatchPolicy, Trie};
+
+/// Which puzzle part to calibrate for: part 1 only counts numeric characters, while part 2 also
+/// counts digits spelled out as words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationMode {
+    Digits,
+    DigitsAndWords,
+}
+
+impl CalibrationMode {
+    // Whether a key found in a line counts as a digit in this mode
+    fn accepts(self, key: &str) -> bool {
+        match self {
+            CalibrationMode::Digits => key.chars().all(|ch| ch.is_ascii_digit()),
+            CalibrationMode::DigitsAndWords => true,
+        }
+    }
+}
+
+impl std::str::FromStr for CalibrationMode {
+    type Err = String;
+
+    /// Parse the puzzle part number as given on the command line
+    fn from_str(part: &str) -> Result<CalibrationMode, String> {
+        match part {
+            "1" => Ok(CalibrationMode::Digits),
+            "2" => Ok(CalibrationMode::DigitsAndWords),
+            _ => Err(format!("invalid part {part:?}, expected 1 or 2")),
+        }
+    }
+}
+
+/// Why a line could not be calibrated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalibrationError {
+    EmptyLine,
+    NoDigit,
+    InvalidUtf8(std::str::Utf8Error),
+    // The calibration value doesn't fit a u32
+    Overflow,
+    // Adding the calibration value to the sum of the lines before it overflows a u64
+    SumOverflow,
+}
+
+impl std::fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CalibrationError::EmptyLine => write!(f, "empty line"),
+            CalibrationError::NoDigit => write!(f, "no digit found"),
+            CalibrationError::InvalidUtf8(err) => write!(f, "invalid UTF-8: {err}"),
+            CalibrationError::Overflow => write!(f, "calibration value too large"),
+            CalibrationError::SumOverflow => write!(f, "sum of calibration values too large"),
+        }
+    }
+}
+
+impl std::error::Error for CalibrationError {}
+
+/// A calibration error together with the (1-based) number of the line it happened on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    pub line_no: usize,
+    pub error: CalibrationError,
+}
+
+impl std::fmt::Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line_no, self.error)
+    }
+}
+
+impl std::error::Error for LineError {}
+
+/// What to do with a line that can't be calibrated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop at the first bad line
+    Strict,
+    /// Count the line as 0
+    Lenient,
+    /// Leave the line out of the sum
+    Skip,
+}
+
+impl ErrorPolicy {
+    /// Apply the policy to the result of calibrating a line, giving the value to add to the sum or
+    /// `None` if the line should be skipped
+    pub fn handle(
+        self,
+        line_no: usize,
+        result: Result<u32, CalibrationError>,
+    ) -> Result<Option<u32>, LineError> {
+        match (result, self) {
+            (Ok(total), _) => Ok(Some(total)),
+            (Err(error), ErrorPolicy::Strict) => Err(LineError { line_no, error }),
+            (Err(_), ErrorPolicy::Lenient) => Ok(Some(0)),
+            (Err(_), ErrorPolicy::Skip) => Ok(None),
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<ErrorPolicy, String> {
+        match policy {
+            "strict" => Ok(ErrorPolicy::Strict),
+            "lenient" => Ok(ErrorPolicy::Lenient),
+            "skip" => Ok(ErrorPolicy::Skip),
+            _ => Err(format!(
+                "invalid error policy {policy:?}, expected strict, lenient or skip"
+            )),
+        }
+    }
+}
+
+/// How a [`DigitMatcher`] reads lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatcherKind {
+    /// Decode lines into chars and look them up in hash maps
+    #[default]
+    Chars,
+    /// Match the raw bytes of lines with a dense transition table, which avoids decoding UTF-8
+    Bytes,
+}
+
+// The automaton for scanning forwards and the trie of reversed keys for reading backwards, over
+// either chars or bytes
+#[derive(Debug)]
+enum Automata {
+    Chars {
+        forward: AhoCorasick<char, u32>,
+        reversed: FrozenTrie<char, u32>,
+    },
+    Bytes {
+        forward: DenseAutomaton<u32>,
+        reversed: FrozenTrie<u8, u32>,
+    },
+}
+
+/// Finds the digits of a vocabulary in a line: the first one by scanning forwards with an
+/// Aho-Corasick automaton and the last one by reading backwards from the end of the line
+///
+/// The digits of a line are ordered by where they start. When some key of the vocabulary occurs
+/// inside another, the [`MatchPolicy`] of the options decides which of the keys starting at the
+/// same place count, and the line is scanned for every match to find them
+#[derive(Debug)]
+pub struct DigitMatcher {
+    automata: Automata,
+    options: MatchOptions,
+    // Whether some key occurs inside another, see Trie::has_nested_keys
+    nested: bool,
+    // Finds whole numbers instead of digits when the options ask for them
+    numbers: Option<NumberParser>,
+    reducer: Reducer,
+}
+
+impl DigitMatcher {
+    pub fn new(trie: &Trie<char, u32>) -> DigitMatcher {
+        DigitMatcher::with_kind(trie, MatcherKind::default())
+    }
+
+    pub fn with_kind(trie: &Trie<char, u32>, kind: MatcherKind) -> DigitMatcher {
+        DigitMatcher::with_options(trie, kind, MatchOptions::default())
+    }
+
+    /// A matcher that folds both the keys of the trie and each line according to `options`
+    /// before matching, see [`MatchOptions`]
+    pub fn with_options(
+        trie: &Trie<char, u32>,
+        kind: MatcherKind,
+        options: MatchOptions,
+    ) -> DigitMatcher {
+        let folded;
+        let trie = if options.is_exact() {
+            trie
+        } else {
+            folded = trie.folded(&options);
+            &folded
+        };
+        let automata = match kind {
+            MatcherKind::Chars => Automata::Chars {
+                forward: AhoCorasick::new(trie),
+                reversed: freeze_reversed(trie),
+            },
+            MatcherKind::Bytes => {
+                let bytes = trie.to_bytes();
+                Automata::Bytes {
+                    forward: DenseAutomaton::new(&bytes),
+                    reversed: freeze_reversed(&bytes),
+                }
+            }
+        };
+        let nested = trie.has_nested_keys();
+        // Zero is a digit when the vocabulary has the numeral 0, see Trie::with_vocabulary
+        let numbers = match (options.whole_numbers, trie.contains_key("0".chars())) {
+            (false, _) => None,
+            (true, false) => Some(NumberParser::new()),
+            (true, true) => Some(NumberParser::with_zero()),
+        };
+        DigitMatcher {
+            automata,
+            options,
+            nested,
+            numbers,
+            reducer: Reducer::default(),
+        }
+    }
+
+    /// Make calibration values with `reducer` instead of from the first and last digit
+    pub fn with_reducer(self, reducer: Reducer) -> DigitMatcher {
+        DigitMatcher { reducer, ..self }
+    }
+
+    pub fn options(&self) -> &MatchOptions {
+        &self.options
+    }
+
+    pub fn reducer(&self) -> Reducer {
+        self.reducer
+    }
+
+    /// Every digit of a line in order of where they start, as fed to the [`Reducer`]
+    pub fn digits(&self, line: &str, mode: CalibrationMode) -> Vec<Match<u32>> {
+        if self.options.is_exact() {
+            return self.exact_digits(line, mode);
+        }
+        let folded = FoldedLine::new(line, &self.options);
+        let digits = self.exact_digits(folded.text(), mode);
+        digits.into_iter().map(|m| folded.to_original(m)).collect()
+    }
+
+    /// Find the first digit of a line by scanning it forwards
+    pub fn first_digit(&self, line: &str, mode: CalibrationMode) -> Option<Match<u32>> {
+        if self.options.is_exact() {
+            return self.first_exact_digit(line, mode);
+        }
+        let folded = FoldedLine::new(line, &self.options);
+        let m = self.first_exact_digit(folded.text(), mode)?;
+        Some(folded.to_original(m))
+    }
+
+    /// Find the last digit of a line by walking the trie of reversed keys (see
+    /// [`Trie::reversed`], kept as a [`FrozenTrie`]) from each position at the end of the line
+    /// backwards, so only the tail of the line is ever read
+    pub fn last_digit(&self, line: &str, mode: CalibrationMode) -> Option<Match<u32>> {
+        if self.options.is_exact() {
+            return self.last_exact_digit(line, mode);
+        }
+        let folded = FoldedLine::new(line, &self.options);
+        let m = self.last_exact_digit(folded.text(), mode)?;
+        Some(folded.to_original(m))
+    }
+
+    // The first digit of a line that has already been folded
+    fn first_exact_digit(&self, line: &str, mode: CalibrationMode) -> Option<Match<u32>> {
+        if let Some(numbers) = &self.numbers {
+            return numbers.numbers(line, mode).into_iter().next();
+        }
+        if self.nested {
+            return self.policy_digits(line, mode).into_iter().next();
+        }
+        // Without nested keys no two matches start at the same place, and the first match to
+        // end is also the first to start
+        let accepts = |m: &Match<u32>| mode.accepts(&line[m.start..m.start + m.len]);
+        match &self.automata {
+            Automata::Chars { forward, .. } => forward.matches(line).find(accepts),
+            Automata::Bytes { forward, .. } => forward.matches(line.as_bytes()).find(accepts),
+        }
+    }
+
+    // The last digit of a line that has already been folded
+    fn last_exact_digit(&self, line: &str, mode: CalibrationMode) -> Option<Match<u32>> {
+        if let Some(numbers) = &self.numbers {
+            return numbers.numbers(line, mode).pop();
+        }
+        if self.nested {
+            return self.policy_digits(line, mode).pop();
+        }
+        // Likewise the last match to end is also the last to start
+        match &self.automata {
+            Automata::Chars { reversed, .. } => last_char_digit(reversed, line, mode),
+            Automata::Bytes { reversed, .. } => last_byte_digit(reversed, line, mode),
+        }
+    }
+
+    /// The nodes of the trie walked through to find a digit, from the root down, each with the
+    /// symbols read to get there and the value it holds if any. The forward automaton spells a
+    /// digit from its start, but the last digit may have been found by reading the trie of
+    /// reversed keys from its end, in which case that is the path given. `None` for whole
+    /// numbers, which are parsed without the trie
+    pub fn trie_path(
+        &self,
+        line: &str,
+        m: &Match<u32>,
+        last: bool,
+    ) -> Option<Vec<(String, Option<u32>)>> {
+        if self.numbers.is_some() {
+            return None;
+        }
+        // Like in locate_digits and last_exact_digit
+        let backwards = last && self.reducer == Reducer::FirstLast && !self.nested;
+        let key = self.options.fold_str(&line[m.start..m.start + m.len]);
+        let path = match &self.automata {
+            Automata::Chars { forward, reversed } => {
+                let (syms, vals): (Vec<char>, _) = if backwards {
+                    (
+                        key.chars().rev().collect(),
+                        reversed.walk(key.chars().rev()),
+                    )
+                } else {
+                    (key.chars().collect(), forward.walk(key.chars()))
+                };
+                (1..)
+                    .zip(vals)
+                    .map(|(read, val)| (syms[..read].iter().collect(), val))
+                    .collect()
+            }
+            Automata::Bytes { forward, reversed } => {
+                let (syms, vals): (Vec<u8>, _) = if backwards {
+                    (
+                        key.bytes().rev().collect(),
+                        reversed.walk(key.bytes().rev()),
+                    )
+                } else {
+                    (key.bytes().collect(), forward.walk(key.as_bytes()))
+                };
+                (1..)
+                    .zip(vals)
+                    .map(|(read, val)| (String::from_utf8_lossy(&syms[..read]).into_owned(), val))
+                    .collect()
+            }
+        };
+        Some(path)
+    }
+
+    // With whole numbers, fail on a line with a numeral too large to read rather than leave it out
+    fn check_numerals(&self, line: &str) -> Result<(), CalibrationError> {
+        let Some(numbers) = &self.numbers else {
+            return Ok(());
+        };
+        if self.options.is_exact() {
+            return numbers.check_numerals(line);
+        }
+        numbers.check_numerals(FoldedLine::new(line, &self.options).text())
+    }
+
+    // Every digit of a line that has already been folded
+    fn exact_digits(&self, line: &str, mode: CalibrationMode) -> Vec<Match<u32>> {
+        match &self.numbers {
+            Some(numbers) => numbers.numbers(line, mode),
+            None => self.policy_digits(line, mode),
+        }
+    }
+
+    // Every digit of a line that has already been folded, in order of where they start, keeping
+    // the ones the match policy picks among those starting at the same place
+    fn policy_digits(&self, line: &str, mode: CalibrationMode) -> Vec<Match<u32>> {
+        let accepts = |m: &Match<u32>| mode.accepts(&line[m.start..m.start + m.len]);
+        let mut digits: Vec<Match<u32>> = match &self.automata {
+            Automata::Chars { forward, .. } => forward.matches(line).filter(accepts).collect(),
+            Automata::Bytes { forward, .. } => {
+                forward.matches(line.as_bytes()).filter(accepts).collect()
+            }
+        };
+        digits.sort_unstable_by_key(|m| (m.start, m.len));
+        match self.options.policy {
+            MatchPolicy::Shortest => digits.dedup_by_key(|m| m.start),
+            MatchPolicy::Longest => {
+                digits.reverse();
+                digits.dedup_by_key(|m| m.start);
+                digits.reverse();
+            }
+            MatchPolicy::All => {}
+        }
+        digits
+    }
+}
+
+// The trie of reversed keys for reading lines backwards. Like in the forward automaton, the
+// empty key is left out, as it would be found everywhere without reading anything
+fn freeze_reversed<K: Eq + std::hash::Hash + Ord + Clone>(
+    trie: &Trie<K, u32>,
+) -> FrozenTrie<K, u32> {
+    let mut reversed = trie.reversed();
+    reversed.remove(std::iter::empty());
+    reversed.freeze()
+}
+
+fn last_char_digit(
+    reversed: &FrozenTrie<char, u32>,
+    line: &str,
+    mode: CalibrationMode,
+) -> Option<Match<u32>> {
+    let mut chars = line.chars();
+    loop {
+        if let (Some(digit), read_count) = reversed.get_digit(&mut chars.clone().rev(), 0) {
+            // The key is the last read_count characters of what is left of the line
+            let rest = chars.as_str();
+            let start = rest
+                .char_indices()
+                .rev()
+                .nth(read_count as usize - 1)
+                .map_or(0, |(i, _)| i);
+            if mode.accepts(&rest[start..]) {
+                break Some(Match {
+                    value: digit,
+                    start,
+                    len: rest.len() - start,
+                });
+            }
+        }
+        chars.next_back()?;
+    }
+}
+
+// Keys are whole UTF-8 sequences, so any match of one in the bytes of a line starts and ends on
+// char boundaries and we can step back a byte at a time
+fn last_byte_digit(
+    reversed: &FrozenTrie<u8, u32>,
+    line: &str,
+    mode: CalibrationMode,
+) -> Option<Match<u32>> {
+    let bytes = line.as_bytes();
+    let mut end = bytes.len();
+    loop {
+        let mut tail = bytes[..end].iter().rev().copied();
+        if let (Some(digit), read_count) = reversed.get_digit(&mut tail, 0) {
+            let start = end - read_count as usize;
+            if mode.accepts(&line[start..end]) {
+                break Some(Match {
+                    value: digit,
+                    start,
+                    len: end - start,
+                });
+            }
+        }
+        end = end.checked_sub(1)?;
+    }
+}
+
+impl Default for DigitMatcher {
+    /// A matcher for the digits one through nine, see [`Trie::with_digits`]
+    fn default() -> DigitMatcher {
+        DigitMatcher::new(&Trie::with_digits())
+    }
+}
+
+/// The first and last digits found in a line, and the calibration value the matcher's
+/// [`Reducer`] made of them and any digits in between
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration {
+    pub first: Match<u32>,
+    pub last: Match<u32>,
+    value: u32,
+}
+
+impl Calibration {
+    /// The calibration value, by default the first and last digit written one after the other
+    /// so that 2 and 9 give 29. Whole numbers are joined the same way, so 23 and 7 give 237
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+/// Find the first and last digits of a line
+pub fn locate_digits(
+    matcher: &DigitMatcher,
+    line: &str,
+    mode: CalibrationMode,
+) -> Result<Calibration, CalibrationError> {
+    if line.is_empty() {
+        return Err(CalibrationError::EmptyLine);
+    }
+    matcher.check_numerals(line)?;
+
+    // Only the first and last digit matter to the puzzle's own rule, and they can be found
+    // without looking at the rest of the line
+    if matcher.reducer == Reducer::FirstLast {
+        let first = matcher
+            .first_digit(line, mode)
+            .ok_or(CalibrationError::NoDigit)?;
+        let last = matcher
+            .last_digit(line, mode)
+            .unwrap_or_else(|| first.clone());
+        let value = Reducer::FirstLast.reduce([first.value, last.value])?;
+        return Ok(Calibration { first, last, value });
+    }
+
+    let digits = matcher.digits(line, mode);
+    let value = matcher.reducer.reduce(digits.iter().map(|m| m.value))?;
+    let first = digits[0].clone();
+    let last = digits[digits.len() - 1].clone();
+    Ok(Calibration { first, last, value })
+}
+
+/// Find the first and last digits of a line of raw input, which has yet to be checked for being
+/// valid UTF-8
+pub fn locate_digits_bytes(
+    matcher: &DigitMatcher,
+    line: &[u8],
+    mode: CalibrationMode,
+) -> Result<Calibration, CalibrationError> {
+    let line = std::str::from_utf8(line).map_err(CalibrationError::InvalidUtf8)?;
+    locate_digits(matcher, line, mode)
+}
+
+/// The calibration value of a line: its first and last digit as a two digit number
+///
+/// ```
+/// use day1::{calibrate, CalibrationMode, DigitMatcher};
+///
+/// let matcher = DigitMatcher::default();
+/// assert_eq!(calibrate(&matcher, "two1nine", CalibrationMode::DigitsAndWords), Ok(29));
+/// assert_eq!(calibrate(&matcher, "two1nine", CalibrationMode::Digits), Ok(11));
+/// ```
+pub fn calibrate(
+    matcher: &DigitMatcher,
+    line: &str,
+    mode: CalibrationMode,
+) -> Result<u32, CalibrationError> {
+    locate_digits(matcher, line, mode).map(|calibration| calibration.value())
+}
+
+/// Totals over all the lines of an input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of lines read
+    pub lines: usize,
+    /// The sum of the calibration values
+    pub sum: u64,
+    /// The number of lines that could not be calibrated
+    pub errors: usize,
+}
+
+impl Summary {
+    /// Count the result of calibrating a line into the totals, handling a bad line according to
+    /// `policy`. Gives what the line added to the sum, or `None` if it was skipped. Fails whatever
+    /// the policy if the sum no longer fits a `u64`
+    pub fn add(
+        &mut self,
+        line_no: usize,
+        result: Result<u32, CalibrationError>,
+        policy: ErrorPolicy,
+    ) -> Result<Option<u32>, LineError> {
+        self.lines = line_no;
+        self.errors += result.is_err() as usize;
+        let value = policy.handle(line_no, result)?;
+        self.sum = self
+            .sum
+            .checked_add(value.unwrap_or(0).into())
+            .ok_or(LineError {
+                line_no,
+                error: CalibrationError::SumOverflow,
+            })?;
+        Ok(value)
+    }
+}
+
+/// Calibrate every line of raw input and sum up the values, handling bad lines according to
+/// `policy`
+pub fn sum_calibrations(
+    matcher: &DigitMatcher,
+    input: &[u8],
+    mode: CalibrationMode,
+    policy: ErrorPolicy,
+) -> Result<Summary, LineError> {
+    let mut summary = Summary::default();
+    for (line_no, line) in (1..).zip(lines(input)) {
+        let result = locate_digits_bytes(matcher, line, mode).map(|c| c.value());
+        summary.add(line_no, result, policy)?;
+    }
+    Ok(summary)
+}
+
+/// Split raw input into lines the same way `str::lines` does, without a trailing `"\n"` or
+/// `"\r\n"`
+pub fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    input.split_inclusive(|&b| b == b'\n').map(|line| {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        line.strip_suffix(b"\r").unwrap_or(line)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibrate() {
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::DigitsAndWords;
+        assert_eq!(calibrate(&matcher, "1abc2", mode), Ok(12));
+        assert_eq!(calibrate(&matcher, "pqr3stu8vwx", mode), Ok(38));
+        assert_eq!(calibrate(&matcher, "a1b2c3d4e5f", mode), Ok(15));
+        assert_eq!(calibrate(&matcher, "treb7uchet", mode), Ok(77));
+        assert_eq!(calibrate(&matcher, "treb7uchet", mode), Ok(77));
+        assert_eq!(calibrate(&matcher, "two1nine", mode), Ok(29));
+        assert_eq!(calibrate(&matcher, "eightwothree", mode), Ok(83));
+        assert_eq!(calibrate(&matcher, "abcone2threexyz", mode), Ok(13));
+        assert_eq!(calibrate(&matcher, "xtwone3four", mode), Ok(24));
+        assert_eq!(calibrate(&matcher, "zoneight234", mode), Ok(14));
+        assert_eq!(calibrate(&matcher, "7pqrstsixteen", mode), Ok(76));
+        assert_eq!(calibrate(&matcher, "4nineeightseven2", mode), Ok(42));
+        assert_eq!(calibrate(&matcher, "53sdthreeninexrfone", mode), Ok(51));
+        assert_eq!(calibrate(&matcher, "threseven9", mode), Ok(79));
+        assert_eq!(calibrate(&matcher, "2hreseven98", mode), Ok(28));
+        assert_eq!(calibrate(&matcher, "thresevennin", mode), Ok(77));
+        assert_eq!(
+            calibrate(&matcher, "hwqesaasd", mode),
+            Err(CalibrationError::NoDigit)
+        );
+        assert_eq!(
+            calibrate(&matcher, "fjdsgcsqppzdthreefour3one3lvmpm", mode),
+            Ok(33)
+        );
+    }
+
+    #[test]
+    fn test_calibrate_part1() {
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::Digits;
+        assert_eq!(calibrate(&matcher, "1abc2", mode), Ok(12));
+        assert_eq!(calibrate(&matcher, "pqr3stu8vwx", mode), Ok(38));
+        assert_eq!(calibrate(&matcher, "a1b2c3d4e5f", mode), Ok(15));
+        assert_eq!(calibrate(&matcher, "treb7uchet", mode), Ok(77));
+        assert_eq!(calibrate(&matcher, "two1nine", mode), Ok(11));
+        assert_eq!(calibrate(&matcher, "xtwone3four", mode), Ok(33));
+        assert_eq!(calibrate(&matcher, "zoneight234", mode), Ok(24));
+        assert_eq!(
+            calibrate(&matcher, "eightwothree", mode),
+            Err(CalibrationError::NoDigit)
+        );
+    }
+
+    #[test]
+    fn test_calibration_errors() {
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::DigitsAndWords;
+        assert_eq!(
+            calibrate(&matcher, "", mode),
+            Err(CalibrationError::EmptyLine)
+        );
+        assert_eq!(
+            locate_digits_bytes(&matcher, b"two1", mode).map(|c| c.value()),
+            Ok(21)
+        );
+        assert!(matches!(
+            locate_digits_bytes(&matcher, b"two\xff1", mode),
+            Err(CalibrationError::InvalidUtf8(_))
+        ));
+
+        let no_digit = || Err(CalibrationError::NoDigit);
+        assert_eq!(ErrorPolicy::Strict.handle(3, Ok(12)), Ok(Some(12)));
+        assert_eq!(
+            ErrorPolicy::Strict.handle(3, no_digit()),
+            Err(LineError {
+                line_no: 3,
+                error: CalibrationError::NoDigit
+            })
+        );
+        assert_eq!(ErrorPolicy::Lenient.handle(3, no_digit()), Ok(Some(0)));
+        assert_eq!(ErrorPolicy::Skip.handle(3, no_digit()), Ok(None));
+    }
+
+    #[test]
+    fn test_lines() {
+        let split: Vec<&[u8]> = lines(b"one\r\ntwo\n\nthree").collect();
+        assert_eq!(split, vec![&b"one"[..], b"two", b"", b"three"]);
+        let split: Vec<&[u8]> = lines(b"one\n").collect();
+        assert_eq!(split, vec![&b"one"[..]]);
+        assert_eq!(lines(b"").count(), 0);
+    }
+
+    #[test]
+    fn test_last_digit() {
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::DigitsAndWords;
+        let last = |line| matcher.last_digit(line, mode).map(|m| m.value);
+        assert_eq!(
+            matcher.last_digit("eightwo", mode),
+            Some(Match {
+                value: 2,
+                start: 4,
+                len: 3
+            })
+        );
+        assert_eq!(last("xtwone3four"), Some(4));
+        assert_eq!(last("7pqrstsixteen"), Some(6));
+        assert_eq!(last("twone"), Some(1));
+        assert_eq!(last("hwqesaasd"), None);
+        assert_eq!(last(""), None);
+
+        let mode = CalibrationMode::Digits;
+        let last = |line| matcher.last_digit(line, mode).map(|m| m.value);
+        assert_eq!(last("xtwone3four"), Some(3));
+        assert_eq!(last("7pqrstsixteen"), Some(7));
+        assert_eq!(last("twone"), None);
+    }
+
+    // Compare finding the last digit by scanning the whole line forwards against reading it
+    // backwards from the end. Run with `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_last_digit() {
+        let input =
+            std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+        let matcher = DigitMatcher::default();
+        let automaton = AhoCorasick::new(&Trie::with_digits());
+        let mode = CalibrationMode::DigitsAndWords;
+        let rounds = 200;
+
+        let start = std::time::Instant::now();
+        let mut forward_sum = 0;
+        for _ in 0..rounds {
+            for line in input.lines() {
+                forward_sum += automaton.matches(line).last().map_or(0, |m| m.value);
+            }
+        }
+        let forward = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let mut backward_sum = 0;
+        for _ in 0..rounds {
+            for line in input.lines() {
+                backward_sum += matcher.last_digit(line, mode).map_or(0, |m| m.value);
+            }
+        }
+        let backward = start.elapsed();
+
+        assert_eq!(forward_sum, backward_sum);
+        println!("forward scan: {forward:?}, reverse scan: {backward:?} ({rounds} rounds)");
+    }
+
+    #[test]
+    fn test_byte_matcher() {
+        let chars = DigitMatcher::with_kind(&Trie::with_digits(), MatcherKind::Chars);
+        let bytes = DigitMatcher::with_kind(&Trie::with_digits(), MatcherKind::Bytes);
+        let lines = [
+            "two1nine",
+            "eightwothree",
+            "xtwone3four",
+            "zoneight234",
+            "7pqrstsixteen",
+            "äsix4ü",
+            "ñine9€eight",
+            "hwqesaasd",
+            "",
+        ];
+        for mode in [CalibrationMode::Digits, CalibrationMode::DigitsAndWords] {
+            for line in lines {
+                assert_eq!(
+                    locate_digits(&bytes, line, mode),
+                    locate_digits(&chars, line, mode),
+                    "{line}"
+                );
+            }
+        }
+
+        let mut trie = Trie::new();
+        trie.insert("fünf".chars(), 5);
+        trie.insert("drei".chars(), 3);
+        let matcher = DigitMatcher::with_kind(&trie, MatcherKind::Bytes);
+        assert_eq!(
+            calibrate(&matcher, "äfünfxdreiü", CalibrationMode::DigitsAndWords),
+            Ok(53)
+        );
+    }
+
+    #[test]
+    fn test_match_options() {
+        let ascii = MatchOptions {
+            ascii_case_insensitive: true,
+            ..MatchOptions::default()
+        };
+        let unicode = MatchOptions {
+            unicode_case_insensitive: true,
+            normalize_digits: true,
+            ..MatchOptions::default()
+        };
+        let mode = CalibrationMode::DigitsAndWords;
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let exact = DigitMatcher::with_kind(&Trie::with_digits(), kind);
+            let matcher = DigitMatcher::with_options(&Trie::with_digits(), kind, ascii);
+            assert_eq!(calibrate(&exact, "OneTWO3", mode), Ok(33));
+            assert_eq!(calibrate(&matcher, "OneTWO3", mode), Ok(13));
+            assert_eq!(calibrate(&matcher, "xSeVeNiNe", mode), Ok(79));
+            assert_eq!(
+                calibrate(&matcher, "１abc９", mode),
+                Err(CalibrationError::NoDigit)
+            );
+
+            let matcher = DigitMatcher::with_options(&Trie::with_digits(), kind, unicode);
+            // Full-width digits are three bytes long, and the matches cover all of them
+            let calibration = locate_digits(&matcher, "１abcNINE", mode).unwrap();
+            assert_eq!(calibration.value(), 19);
+            assert_eq!((calibration.first.start, calibration.first.len), (0, 3));
+            assert_eq!((calibration.last.start, calibration.last.len), (6, 4));
+            assert_eq!(calibrate(&matcher, "x²y①", CalibrationMode::Digits), Ok(21));
+
+            let mut trie = Trie::new();
+            trie.insert("fünf".chars(), 5);
+            trie.insert("drei".chars(), 3);
+            let matcher = DigitMatcher::with_options(&trie, kind, ascii);
+            assert_eq!(calibrate(&matcher, "FÜNFDrei", mode), Ok(33));
+            let matcher = DigitMatcher::with_options(&trie, kind, unicode);
+            assert_eq!(calibrate(&matcher, "FÜNFDrei", mode), Ok(53));
+
+            // Folding turns "ß" into "ss" in both the key and the line
+            trie.insert("straße".chars(), 7);
+            let matcher = DigitMatcher::with_options(&trie, kind, unicode);
+            let calibration = locate_digits(&matcher, "drei STRASSE", mode).unwrap();
+            assert_eq!(calibration.value(), 37);
+            assert_eq!((calibration.last.start, calibration.last.len), (5, 7));
+            assert_eq!(calibrate(&matcher, "Straße", mode), Ok(77));
+        }
+    }
+
+    #[test]
+    fn test_match_policy() {
+        let mut trie = Trie::with_digits();
+        trie.insert("seventeen".chars(), 17);
+        trie.insert("teen".chars(), 10);
+        let mode = CalibrationMode::DigitsAndWords;
+        let digits = |matcher: &DigitMatcher, line| {
+            let calibration = locate_digits(matcher, line, mode).unwrap();
+            (calibration.first.value, calibration.last.value)
+        };
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let options = |policy| MatchOptions {
+                ascii_case_insensitive: true,
+                policy,
+                ..MatchOptions::default()
+            };
+            let shortest = DigitMatcher::with_options(&trie, kind, options(MatchPolicy::Shortest));
+            let longest = DigitMatcher::with_options(&trie, kind, options(MatchPolicy::Longest));
+            let all = DigitMatcher::with_options(&trie, kind, options(MatchPolicy::All));
+
+            // "teen" starts after "seven" and "seventeen", so it is the last digit either way
+            assert_eq!(digits(&shortest, "xseventeeny"), (7, 10));
+            assert_eq!(digits(&longest, "xseventeeny"), (17, 10));
+            assert_eq!(digits(&all, "xseventeeny"), (7, 10));
+            assert_eq!(digits(&shortest, "SevenTeen2seventeen"), (7, 10));
+            assert_eq!(digits(&longest, "2seventeen"), (2, 10));
+
+            trie.remove("teen".chars());
+            let shortest = DigitMatcher::with_options(&trie, kind, options(MatchPolicy::Shortest));
+            let longest = DigitMatcher::with_options(&trie, kind, options(MatchPolicy::Longest));
+            let all = DigitMatcher::with_options(&trie, kind, options(MatchPolicy::All));
+            assert_eq!(digits(&shortest, "xseventeeny"), (7, 7));
+            assert_eq!(digits(&longest, "xseventeeny"), (17, 17));
+            assert_eq!(digits(&all, "xseventeeny"), (7, 17));
+            assert_eq!(digits(&longest, "seventeenseven"), (17, 7));
+            let calibration = locate_digits(&longest, "a1seventeen", mode).unwrap();
+            assert_eq!((calibration.last.start, calibration.last.len), (2, 9));
+            // Part 1 only counts numerals, which never nest
+            assert_eq!(
+                calibrate(&longest, "seventeen4x5", CalibrationMode::Digits),
+                Ok(45)
+            );
+            trie.insert("teen".chars(), 10);
+        }
+
+        // Without nested keys all policies agree
+        for line in ["two1nine", "eightwothree", "xtwone3four", "7pqrstsixteen"] {
+            let calibrations: Vec<_> = [
+                MatchPolicy::Shortest,
+                MatchPolicy::Longest,
+                MatchPolicy::All,
+            ]
+            .map(|policy| {
+                let options = MatchOptions {
+                    policy,
+                    ..MatchOptions::default()
+                };
+                let matcher =
+                    DigitMatcher::with_options(&Trie::with_digits(), MatcherKind::Chars, options);
+                locate_digits(&matcher, line, mode)
+            })
+            .into();
+            assert!(
+                calibrations.windows(2).all(|pair| pair[0] == pair[1]),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_whole_numbers() {
+        let options = MatchOptions {
+            ascii_case_insensitive: true,
+            whole_numbers: true,
+            ..MatchOptions::default()
+        };
+        let mode = CalibrationMode::DigitsAndWords;
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let matcher = DigitMatcher::with_options(&Trie::with_digits(), kind, options);
+            assert_eq!(calibrate(&matcher, "sixteen", mode), Ok(1616));
+            assert_eq!(calibrate(&matcher, "Twenty-Three x 7", mode), Ok(237));
+            assert_eq!(
+                calibrate(&matcher, "4 and one hundred and five", mode),
+                Ok(4105)
+            );
+            assert_eq!(calibrate(&matcher, "eightwo", mode), Ok(82));
+            assert_eq!(
+                calibrate(&matcher, "twelve 10 twenty", CalibrationMode::Digits),
+                Ok(1010)
+            );
+            assert_eq!(
+                calibrate(&matcher, "twelve", CalibrationMode::Digits),
+                Err(CalibrationError::NoDigit)
+            );
+            assert_eq!(calibrate(&matcher, "1234", mode), Ok(12341234));
+            assert_eq!(calibrate(&matcher, "x6789", mode), Ok(67896789));
+            assert_eq!(
+                calibrate(&matcher, "123456", mode),
+                Err(CalibrationError::Overflow)
+            );
+            assert_eq!(
+                calibrate(&matcher, "one 99999999999", mode),
+                Err(CalibrationError::Overflow)
+            );
+
+            let calibration = locate_digits(&matcher, "x Forty-Two", mode).unwrap();
+            assert_eq!((calibration.first.start, calibration.first.len), (2, 9));
+        }
+
+        // Digits are still joined into two digit numbers
+        let matcher = DigitMatcher::default();
+        assert_eq!(calibrate(&matcher, "twenty-three", mode), Ok(33));
+        assert_eq!(calibrate(&matcher, "sixteen", mode), Ok(66));
+    }
+
+    #[test]
+    fn test_empty_key() {
+        let mut trie = Trie::with_digits();
+        trie.insert("".chars(), 0);
+        let mode = CalibrationMode::DigitsAndWords;
+        let folding = MatchOptions {
+            ascii_case_insensitive: true,
+            ..MatchOptions::default()
+        };
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            for options in [MatchOptions::default(), folding] {
+                for reducer in [Reducer::FirstLast, Reducer::Concat] {
+                    let matcher =
+                        DigitMatcher::with_options(&trie, kind, options).with_reducer(reducer);
+                    assert_eq!(
+                        calibrate(&matcher, "xab", mode),
+                        Err(CalibrationError::NoDigit)
+                    );
+                    assert_eq!(calibrate(&matcher, "a1two", mode), Ok(12));
+                    let explanation = matcher.explain("two", mode);
+                    assert!(explanation
+                        .to_string()
+                        .contains("digits: two = 2 at 0..3\n"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reducers() {
+        let mode = CalibrationMode::DigitsAndWords;
+        let line = "a2bseven1eightwo";
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let matcher =
+                |reducer| DigitMatcher::with_kind(&Trie::with_digits(), kind).with_reducer(reducer);
+            let value = |reducer| calibrate(&matcher(reducer), line, mode);
+            assert_eq!(value(Reducer::FirstLast), Ok(22));
+            assert_eq!(value(Reducer::Sum), Ok(20));
+            assert_eq!(value(Reducer::Concat), Ok(27182));
+            assert_eq!(value(Reducer::Max), Ok(8));
+            assert_eq!(value(Reducer::Min), Ok(1));
+            assert_eq!(value(Reducer::FirstK(2)), Ok(27));
+            assert_eq!(
+                calibrate(&matcher(Reducer::Concat), "98765432198", mode),
+                Err(CalibrationError::Overflow)
+            );
+            assert_eq!(
+                calibrate(&matcher(Reducer::Sum), "abc", mode),
+                Err(CalibrationError::NoDigit)
+            );
+            assert_eq!(
+                calibrate(&matcher(Reducer::Sum), line, CalibrationMode::Digits),
+                Ok(3)
+            );
+
+            let calibration = locate_digits(&matcher(Reducer::Max), line, mode).unwrap();
+            assert_eq!((calibration.first.value, calibration.first.start), (2, 1));
+            assert_eq!((calibration.last.value, calibration.last.start), (2, 13));
+        }
+
+        let options = MatchOptions {
+            whole_numbers: true,
+            ..MatchOptions::default()
+        };
+        let matcher = DigitMatcher::with_options(&Trie::with_digits(), MatcherKind::Chars, options)
+            .with_reducer(Reducer::Sum);
+        assert_eq!(calibrate(&matcher, "twenty-three 100 and 4", mode), Ok(127));
+    }
+
+    #[test]
+    fn test_unicode_digits() {
+        let options = MatchOptions {
+            unicode_digits: true,
+            ..MatchOptions::default()
+        };
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let exact = DigitMatcher::with_kind(&Trie::with_digits(), kind);
+            let matcher = DigitMatcher::with_options(&Trie::with_digits(), kind, options);
+            for mode in [CalibrationMode::Digits, CalibrationMode::DigitsAndWords] {
+                assert_eq!(
+                    calibrate(&exact, "x٣y७z", mode),
+                    Err(CalibrationError::NoDigit)
+                );
+                // Arabic-Indic three and Devanagari seven, two and three bytes long
+                let calibration = locate_digits(&matcher, "x٣y७z", mode).unwrap();
+                assert_eq!(calibration.value(), 37);
+                assert_eq!((calibration.first.start, calibration.first.len), (1, 2));
+                assert_eq!((calibration.last.start, calibration.last.len), (4, 3));
+                // Thai five and Bengali three
+                assert_eq!(calibrate(&matcher, "๕x৩", mode), Ok(53));
+            }
+            assert_eq!(
+                calibrate(&matcher, "one٢", CalibrationMode::DigitsAndWords),
+                Ok(12)
+            );
+        }
+    }
+
+    #[test]
+    fn test_summary_sum_overflow() {
+        let matcher = DigitMatcher::default().with_reducer(Reducer::Concat);
+        let input = b"999999999\n999999999\n999999999\n999999999\n999999999\n";
+        let summary = sum_calibrations(
+            &matcher,
+            input,
+            CalibrationMode::Digits,
+            ErrorPolicy::Strict,
+        );
+        assert_eq!(summary.map(|summary| summary.sum), Ok(4_999_999_995));
+
+        let mut summary = Summary {
+            lines: 1,
+            sum: u64::MAX - 5,
+            errors: 0,
+        };
+        assert_eq!(summary.add(2, Ok(5), ErrorPolicy::Strict), Ok(Some(5)));
+        assert_eq!(
+            summary.add(3, Ok(1), ErrorPolicy::Lenient),
+            Err(LineError {
+                line_no: 3,
+                error: CalibrationError::SumOverflow
+            })
+        );
+        assert_eq!(summary.sum, u64::MAX);
+    }
+
+    // Compare calibrating by decoding chars against matching raw bytes. Run with
+    // `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_matcher_kinds() {
+        let input = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+        let mode = CalibrationMode::DigitsAndWords;
+        let rounds = 200;
+
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let matcher = DigitMatcher::with_kind(&Trie::with_digits(), kind);
+            let start = std::time::Instant::now();
+            for _ in 0..rounds {
+                let summary = sum_calibrations(&matcher, &input, mode, ErrorPolicy::Strict);
+                assert_eq!(summary.map(|summary| summary.sum), Ok(55429));
+            }
+            println!("{kind:?}: {:?} ({rounds} rounds)", start.elapsed());
+        }
+    }
+}
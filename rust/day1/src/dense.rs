@@ -0,0 +1,141 @@
+use crate::automaton::{AhoCorasick, Match};
+use crate::trie::Trie;
+
+/// An Aho-Corasick automaton over bytes with every transition worked out ahead of time, so that
+/// scanning a line is a single table lookup per byte with no failure links to follow and no
+/// UTF-8 decoding. Each state takes a row of 256 entries in the table, which is plenty small for
+/// vocabularies like the digits
+#[derive(Debug)]
+pub struct DenseAutomaton<V> {
+    // The next state for every state and byte, at state * 256 + byte
+    table: Vec<u32>,
+    // Per state: the value of the key ending there, its length in bytes, and the next state along
+    // the failure chain holding a value
+    vals: Vec<Option<V>>,
+    depths: Vec<usize>,
+    outputs: Vec<Option<u32>>,
+    // Per state, the first state of its output chain, i.e. itself if it holds a value
+    first_outputs: Vec<Option<u32>>,
+}
+
+impl<V: Clone> DenseAutomaton<V> {
+    pub fn new(trie: &Trie<u8, V>) -> DenseAutomaton<V> {
+        let sparse = AhoCorasick::new(trie);
+        let states = sparse.nodes.len();
+
+        let mut table = Vec::with_capacity(states * 256);
+        for state in 0..states {
+            for byte in 0..=u8::MAX {
+                table.push(AhoCorasick::goto(&sparse.nodes, state, &byte) as u32);
+            }
+        }
+
+        DenseAutomaton {
+            table,
+            vals: sparse.nodes.iter().map(|node| node.val.clone()).collect(),
+            depths: sparse.nodes.iter().map(|node| node.depth).collect(),
+            outputs: sparse
+                .nodes
+                .iter()
+                .map(|node| node.output.map(|idx| idx as u32))
+                .collect(),
+            first_outputs: (0..states)
+                .map(|state| sparse.first_output(state).map(|idx| idx as u32))
+                .collect(),
+        }
+    }
+
+    // The same as AhoCorasick::walk. A transition that doesn't go one level deeper followed a
+    // failure link, which means the key left the trie
+    pub(crate) fn walk(&self, key: &[u8]) -> Vec<Option<V>> {
+        let mut state = 0;
+        let mut path = Vec::new();
+        for &byte in key {
+            let next = self.table[state * 256 + byte as usize] as usize;
+            if self.depths[next] != path.len() + 1 {
+                break;
+            }
+            state = next;
+            path.push(self.vals[state].clone());
+        }
+        path
+    }
+
+    /// Iterate over every key found in the line, in the same order as
+    /// [`AhoCorasick::matches`], with offsets in bytes
+    pub fn matches<'a>(&'a self, line: &'a [u8]) -> DenseMatches<'a, V> {
+        DenseMatches {
+            automaton: self,
+            bytes: line.iter().enumerate(),
+            state: 0,
+            end: 0,
+            out: None,
+        }
+    }
+}
+
+/// Iterator over the matches in a line, see [`DenseAutomaton::matches`]
+pub struct DenseMatches<'a, V> {
+    automaton: &'a DenseAutomaton<V>,
+    bytes: std::iter::Enumerate<std::slice::Iter<'a, u8>>,
+    state: u32,
+    // Byte offset just past the current byte
+    end: usize,
+    // The next state in the output chain of the current state that we have yet to report
+    out: Option<u32>,
+}
+
+impl<V: Clone> Iterator for DenseMatches<'_, V> {
+    type Item = Match<V>;
+
+    fn next(&mut self) -> Option<Match<V>> {
+        loop {
+            if let Some(idx) = self.out {
+                let idx = idx as usize;
+                self.out = self.automaton.outputs[idx];
+                let len = self.automaton.depths[idx];
+                return Some(Match {
+                    value: self.automaton.vals[idx]
+                        .clone()
+                        .expect("output states always hold a value"),
+                    start: self.end - len,
+                    len,
+                });
+            }
+
+            let (offset, &byte) = self.bytes.next()?;
+            self.end = offset + 1;
+            self.state = self.automaton.table[self.state as usize * 256 + byte as usize];
+            self.out = self.automaton.first_outputs[self.state as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_matches_sparse() {
+        let trie = Trie::with_digits();
+        let sparse = AhoCorasick::new(&trie);
+        let dense = DenseAutomaton::new(&trie.to_bytes());
+        for line in [
+            "eightwothree",
+            "7twone",
+            "äsix4",
+            "hwqesaasd",
+            "",
+            "oneightwoneight",
+        ] {
+            let expected: Vec<Match<u32>> = sparse.matches(line).collect();
+            let found: Vec<Match<u32>> = dense.matches(line.as_bytes()).collect();
+            assert_eq!(found, expected, "{line}");
+            assert_eq!(
+                dense.walk(line.as_bytes()),
+                sparse.walk(line.bytes().map(char::from)),
+                "{line}"
+            );
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt::Write;
+
+use crate::automaton::Match;
+use crate::calibration::{
+    locate_digits, Calibration, CalibrationError, CalibrationMode, DigitMatcher,
+};
+
+// ANSI escape codes for highlighting the first digit, the last digit and any other digits
+const FIRST_COLOUR: &str = "\x1b[1;32m";
+const LAST_COLOUR: &str = "\x1b[1;34m";
+const OTHER_COLOUR: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// How a line was decoded: every digit found in it and the calibration that came of them, see
+/// [`DigitMatcher::explain`]
+#[derive(Debug)]
+pub struct Explanation<'a> {
+    pub line: &'a str,
+    pub mode: CalibrationMode,
+    /// Every digit of the line in order of where they start, as fed to the reducer
+    pub digits: Vec<Match<u32>>,
+    /// Digits spelled out as words, which don't count in [`CalibrationMode::Digits`]
+    pub skipped_words: Vec<Match<u32>>,
+    pub result: Result<Calibration, CalibrationError>,
+    // The reducer's name
+    reducer: String,
+    // The trie paths to the first and last digit, rendered
+    paths: Option<[String; 2]>,
+}
+
+impl DigitMatcher {
+    /// Explain how a line is calibrated: which digits are found where, the path through the
+    /// trie that spells the first and last one, and why they were chosen
+    pub fn explain<'a>(&self, line: &'a str, mode: CalibrationMode) -> Explanation<'a> {
+        let digits = self.digits(line, mode);
+        let skipped_words = match mode {
+            CalibrationMode::Digits => self
+                .digits(line, CalibrationMode::DigitsAndWords)
+                .into_iter()
+                .filter(|m| !digits.contains(m))
+                .collect(),
+            CalibrationMode::DigitsAndWords => Vec::new(),
+        };
+        let result = locate_digits(self, line, mode);
+        let paths = result.as_ref().ok().and_then(|calibration| {
+            let first = self.trie_path(line, &calibration.first, false)?;
+            let last = self.trie_path(line, &calibration.last, true)?;
+            Some([render_path(first), render_path(last)])
+        });
+        Explanation {
+            line,
+            mode,
+            digits,
+            skipped_words,
+            result,
+            reducer: self.reducer().to_string(),
+            paths,
+        }
+    }
+}
+
+impl Explanation<'_> {
+    /// Write the explanation as lines of text. With `colour` the digits are highlighted in the
+    /// line with ANSI escape codes, otherwise they are marked in a line of their own below it:
+    /// `F` for the first digit, `L` for the last and `~` for any other
+    pub fn render(&self, colour: bool) -> String {
+        let mut out = String::new();
+        let (first, last) = match &self.result {
+            Ok(calibration) => (Some(&calibration.first), Some(&calibration.last)),
+            Err(_) => (None, None),
+        };
+        // The first digit takes precedence where it overlaps the last, like in "eightwo"
+        let mark = |offset: usize| {
+            let covers = |m: &&Match<u32>| (m.start..m.start + m.len).contains(&offset);
+            if first.filter(covers).is_some() {
+                Some(('F', FIRST_COLOUR))
+            } else if last.filter(covers).is_some() {
+                Some(('L', LAST_COLOUR))
+            } else if self.digits.iter().any(|m| covers(&m)) {
+                Some(('~', OTHER_COLOUR))
+            } else {
+                None
+            }
+        };
+
+        if colour {
+            for (offset, ch) in self.line.char_indices() {
+                match mark(offset) {
+                    Some((_, code)) => write!(out, "{code}{ch}{RESET}").unwrap(),
+                    None => out.push(ch),
+                }
+            }
+            out.push('\n');
+        } else {
+            let markers: String = self
+                .line
+                .char_indices()
+                .map(|(offset, _)| mark(offset).map_or(' ', |(marker, _)| marker))
+                .collect();
+            writeln!(out, "{}\n{}", self.line, markers.trim_end()).unwrap();
+        }
+
+        let describe = |m: &Match<u32>| {
+            format!(
+                "{} = {} at {}..{}",
+                &self.line[m.start..m.start + m.len],
+                m.value,
+                m.start,
+                m.start + m.len
+            )
+        };
+        let digits: Vec<String> = self.digits.iter().map(describe).collect();
+        writeln!(out, "digits: {}", digits.join(", ")).unwrap();
+        if !self.skipped_words.is_empty() {
+            let words: Vec<String> = self.skipped_words.iter().map(describe).collect();
+            writeln!(
+                out,
+                "skipped: {}, spelled out digits don't count in part 1",
+                words.join(", ")
+            )
+            .unwrap();
+        }
+
+        let calibration = match &self.result {
+            Ok(calibration) => calibration,
+            Err(err) => {
+                writeln!(out, "error: {err}").unwrap();
+                return out;
+            }
+        };
+        let reason = |which: &str| {
+            if self.digits.len() == 1 {
+                String::from("the only digit, so both first and last")
+            } else {
+                format!("the {which} of {} digits to start", self.digits.len())
+            }
+        };
+        for (idx, which, m) in [
+            (0, "first", &calibration.first),
+            (1, "last", &calibration.last),
+        ] {
+            write!(out, "{which}: {}, {}", describe(m), reason(which)).unwrap();
+            // Whole numbers are parsed without the trie
+            match &self.paths {
+                Some(paths) => writeln!(out, ", trie path {}", paths[idx]).unwrap(),
+                None => out.push('\n'),
+            }
+        }
+        let values: Vec<String> = self.digits.iter().map(|m| m.value.to_string()).collect();
+        writeln!(
+            out,
+            "value: {}, {} of {}",
+            calibration.value(),
+            self.reducer,
+            values.join(" ")
+        )
+        .unwrap();
+        out
+    }
+}
+
+// Write a path through the trie as the symbols read to get to each node, with the value of the
+// nodes that hold one, like "t → tw → two=2", or "o → ow → owt=2" through the reversed trie.
+// These are the folded keys, so "Two" walks the same path as "two" when ignoring case
+fn render_path(path: Vec<(String, Option<u32>)>) -> String {
+    let nodes: Vec<String> = path
+        .into_iter()
+        .map(|(read, val)| match val {
+            Some(val) => format!("{read}={val}"),
+            None => read,
+        })
+        .collect();
+    nodes.join(" → ")
+}
+
+impl std::fmt::Display for Explanation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.render(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::MatcherKind;
+    use crate::normalize::MatchOptions;
+    use crate::reduce::Reducer;
+    use crate::trie::{MatchPolicy, Trie};
+
+    #[test]
+    fn test_explain() {
+        let matcher = DigitMatcher::default();
+        let explanation = matcher.explain("xtwo1nine", CalibrationMode::DigitsAndWords);
+        assert_eq!(explanation.digits.len(), 3);
+        assert_eq!(
+            explanation.to_string(),
+            "xtwo1nine\n FFF~LLLL\n\
+             digits: two = 2 at 1..4, 1 = 1 at 4..5, nine = 9 at 5..9\n\
+             first: two = 2 at 1..4, the first of 3 digits to start, trie path t → tw → two=2\n\
+             last: nine = 9 at 5..9, the last of 3 digits to start, \
+             trie path e → en → eni → enin=9\n\
+             value: 29, first-last of 2 1 9\n"
+        );
+
+        let explanation = matcher.explain("eightwo", CalibrationMode::DigitsAndWords);
+        assert_eq!(
+            explanation.render(true).lines().next().unwrap(),
+            "\x1b[1;32me\x1b[0m\x1b[1;32mi\x1b[0m\x1b[1;32mg\x1b[0m\x1b[1;32mh\x1b[0m\
+             \x1b[1;32mt\x1b[0m\x1b[1;34mw\x1b[0m\x1b[1;34mo\x1b[0m"
+        );
+
+        let explanation = matcher.explain("two7x", CalibrationMode::Digits);
+        assert_eq!(
+            explanation.to_string(),
+            "two7x\n   F\n\
+             digits: 7 = 7 at 3..4\n\
+             skipped: two = 2 at 0..3, spelled out digits don't count in part 1\n\
+             first: 7 = 7 at 3..4, the only digit, so both first and last, trie path 7=7\n\
+             last: 7 = 7 at 3..4, the only digit, so both first and last, trie path 7=7\n\
+             value: 77, first-last of 7\n"
+        );
+
+        let matcher = DigitMatcher::default().with_reducer(Reducer::Sum);
+        let explanation = matcher.explain("abc", CalibrationMode::DigitsAndWords);
+        assert_eq!(explanation.result, Err(CalibrationError::NoDigit));
+        assert_eq!(
+            explanation.to_string(),
+            "abc\n\ndigits: \nerror: no digit found\n"
+        );
+
+        let options = MatchOptions {
+            ascii_case_insensitive: true,
+            ..MatchOptions::default()
+        };
+        let matcher = DigitMatcher::with_options(&Trie::with_digits(), MatcherKind::Chars, options);
+        let explanation = matcher.explain("SIX", CalibrationMode::DigitsAndWords);
+        assert!(explanation.to_string().contains(
+            "first: SIX = 6 at 0..3, the only digit, so both first and last, \
+             trie path s → si → six=6\n"
+        ));
+
+        // With nested keys the last digit is found scanning forwards too, and the path shows
+        // the shorter key it passes
+        let mut trie = Trie::with_digits();
+        trie.insert("seventeen".chars(), 17);
+        let options = MatchOptions {
+            policy: MatchPolicy::Longest,
+            ..MatchOptions::default()
+        };
+        for kind in [MatcherKind::Chars, MatcherKind::Bytes] {
+            let matcher = DigitMatcher::with_options(&trie, kind, options);
+            let explanation = matcher.explain("1seventeen", CalibrationMode::DigitsAndWords);
+            assert!(explanation.to_string().contains(
+                "last: seventeen = 17 at 1..10, the last of 2 digits to start, trie path \
+                 s → se → sev → seve → seven=7 → sevent → sevente → seventee → seventeen=17\n"
+            ));
+            let explanation = matcher.explain("twoxone", CalibrationMode::DigitsAndWords);
+            assert!(
+                explanation
+                    .to_string()
+                    .contains("trie path o → on → one=1\n"),
+                "{kind:?}"
+            );
+        }
+    }
+}
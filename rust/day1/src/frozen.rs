@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::trie::Trie;
+
+// A node of a frozen trie: its edges are the range edges[start..end] of the trie's edge list
+#[derive(Debug)]
+struct FrozenNode<V> {
+    start: u32,
+    end: u32,
+    val: Option<V>,
+}
+
+/// A read-only trie with all its nodes in one vector and all its edges in another, sorted by
+/// symbol within each node, instead of a hash map allocated per node. Built with
+/// [`Trie::freeze`]; lookups walk the same way as on the [`Trie`] it came from
+#[derive(Debug)]
+pub struct FrozenTrie<K, V> {
+    nodes: Vec<FrozenNode<V>>,
+    // The symbol and target node of every edge
+    edges: Vec<(K, u32)>,
+}
+
+impl<K: Eq + Hash + Ord + Clone, V: Clone> Trie<K, V> {
+    /// Copy the trie into the compact [`FrozenTrie`] representation
+    pub fn freeze(&self) -> FrozenTrie<K, V> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        // Lay the nodes out breadth first, so that the children of a node get consecutive
+        // indices in the order their edges are listed in
+        let mut queue = VecDeque::from([self]);
+        while let Some(node) = queue.pop_front() {
+            let mut children: Vec<(&K, &Trie<K, V>)> = node.next.iter().collect();
+            children.sort_unstable_by_key(|&(sym, _)| sym);
+
+            let start = edges.len() as u32;
+            for (sym, child) in children {
+                let child_idx = nodes.len() + queue.len() + 1;
+                edges.push((sym.clone(), child_idx as u32));
+                queue.push_back(child);
+            }
+            nodes.push(FrozenNode {
+                start,
+                end: edges.len() as u32,
+                val: node.val.clone(),
+            });
+        }
+
+        FrozenTrie { nodes, edges }
+    }
+}
+
+impl<K: Ord, V: Clone> FrozenTrie<K, V> {
+    /// Return a value if the next symbols spell one of the keys, otherwise `None`. Also returns
+    /// the number of symbols read. The same as [`Trie::get_digit`]
+    pub fn get_digit<I: Iterator<Item = K>>(
+        &self,
+        syms: &mut I,
+        read_count: u32,
+    ) -> (Option<V>, u32) {
+        let mut node = &self.nodes[0];
+        let mut read_count = read_count;
+        loop {
+            if let Some(val) = &node.val {
+                break (Some(val.clone()), read_count);
+            }
+            let Some(sym) = syms.next() else {
+                break (None, 0);
+            };
+            let edges = &self.edges[node.start as usize..node.end as usize];
+            match edges.binary_search_by(|(edge, _)| edge.cmp(&sym)) {
+                Ok(idx) => node = &self.nodes[edges[idx].1 as usize],
+                Err(_) => break (None, 0),
+            }
+            read_count += 1;
+        }
+    }
+
+    /// The values held by the nodes that [`get_digit`](FrozenTrie::get_digit) walks through
+    /// below the root when reading `syms`, one per symbol read, up to the first node holding a
+    /// value or where the symbols leave the trie
+    pub fn walk<I: Iterator<Item = K>>(&self, syms: I) -> Vec<Option<V>> {
+        let mut node = &self.nodes[0];
+        let mut path = Vec::new();
+        for sym in syms {
+            if node.val.is_some() {
+                break;
+            }
+            let edges = &self.edges[node.start as usize..node.end as usize];
+            match edges.binary_search_by(|(edge, _)| edge.cmp(&sym)) {
+                Ok(idx) => node = &self.nodes[edges[idx].1 as usize],
+                Err(_) => break,
+            }
+            path.push(node.val.clone());
+        }
+        path
+    }
+
+    /// The number of nodes, including the root
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freeze() {
+        let trie = Trie::with_digits();
+        let frozen = trie.freeze();
+        for line in [
+            "seven", "7", "eightwo", "thre", "nine9", "", "x1", "fivefour",
+        ] {
+            assert_eq!(
+                frozen.get_digit(&mut line.chars(), 0),
+                trie.get_digit(&mut line.chars(), 0),
+                "{line}"
+            );
+        }
+        // The root, one node per numeral and the letters of the words, sharing the "t", "f" and
+        // "s" that start two words each
+        assert_eq!(frozen.node_count(), 1 + 9 + 36 - 3);
+
+        let reversed = trie.reversed().freeze();
+        assert_eq!(reversed.walk("owtx".chars()), [None, None, Some(2)]);
+        assert_eq!(reversed.walk("ow".chars()), [None, None]);
+        assert!(reversed.walk("q".chars()).is_empty());
+
+        let frozen = Trie::<u8, u32>::new().freeze();
+        assert_eq!(frozen.get_digit(&mut b"abc".iter().copied(), 0), (None, 0));
+    }
+
+    // Compare looking up the last digit of every line of the input in a hash map based trie of
+    // reversed keys against its frozen copy. Run with
+    // `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_frozen_trie() {
+        let input =
+            std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+        let reversed = Trie::with_digits().reversed();
+        let frozen = reversed.freeze();
+        let rounds = 200;
+
+        let last_digit =
+            |get_digit: &dyn Fn(&mut std::iter::Rev<std::str::Chars>) -> Option<u32>| {
+                let mut sum = 0;
+                for line in input.lines() {
+                    let mut chars = line.chars();
+                    sum += loop {
+                        if let Some(digit) = get_digit(&mut chars.clone().rev()) {
+                            break digit;
+                        }
+                        if chars.next_back().is_none() {
+                            break 0;
+                        }
+                    };
+                }
+                sum
+            };
+
+        let start = std::time::Instant::now();
+        let mut hashed_sum = 0;
+        for _ in 0..rounds {
+            hashed_sum += last_digit(&|chars| reversed.get_digit(chars, 0).0);
+        }
+        let hashed = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let mut frozen_sum = 0;
+        for _ in 0..rounds {
+            frozen_sum += last_digit(&|chars| frozen.get_digit(chars, 0).0);
+        }
+        let frozen = start.elapsed();
+
+        assert_eq!(hashed_sum, frozen_sum);
+        println!("hash map trie: {hashed:?}, frozen trie: {frozen:?} ({rounds} rounds)");
+    }
+}
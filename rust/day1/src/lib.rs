@@ -0,0 +1,40 @@
+//! Solutions to day 1 of Advent of Code 2023: recovering calibration values from lines of text by
+//! combining the first and last digit found on each line, where digits may also be spelled out as
+//! words (`"two1nine"` calibrates to 29).
+//!
+//! Digits are found with a [`Trie`] of their spellings, compiled into an [`AhoCorasick`]
+//! automaton for scanning lines forwards and a reversed trie for reading them from the end, both
+//! wrapped up in a [`DigitMatcher`].
+
+mod audit;
+mod automaton;
+mod calibration;
+mod dense;
+mod explain;
+mod frozen;
+mod normalize;
+mod numbers;
+mod parallel;
+mod reduce;
+mod report;
+mod stream;
+mod trie;
+mod vocab;
+
+pub use audit::{AuditSummary, LineAudit};
+pub use automaton::{AhoCorasick, Match, Matches};
+pub use calibration::{
+    calibrate, lines, locate_digits, locate_digits_bytes, sum_calibrations, Calibration,
+    CalibrationError, CalibrationMode, DigitMatcher, ErrorPolicy, LineError, MatcherKind, Summary,
+};
+pub use dense::{DenseAutomaton, DenseMatches};
+pub use explain::Explanation;
+pub use frozen::FrozenTrie;
+pub use normalize::MatchOptions;
+pub use numbers::NumberParser;
+pub use parallel::sum_calibrations_parallel;
+pub use reduce::Reducer;
+pub use report::{Format, LineRecord, Report, CSV_HEADER};
+pub use stream::{sum_calibrations_reader, LineReader, StreamError};
+pub use trie::{MatchPolicy, Trie, TrieIter};
+pub use vocab::{Vocabulary, VocabularyError};
@@ -1,177 +1,676 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::process::ExitCode;
 
-#[derive(Debug)]
-struct TrieNode {
-    next: std::collections::HashMap<char, TrieNode>,
-    val: Option<u32>,
-}
+use day1::{
+    locate_digits_bytes, sum_calibrations_parallel, AuditSummary, Calibration, CalibrationMode,
+    DigitMatcher, ErrorPolicy, Format, LineAudit, LineReader, LineRecord, MatchOptions,
+    MatcherKind, Reducer, Report, Summary, Trie, Vocabulary,
+};
 
-impl TrieNode {
-    fn new() -> TrieNode {
-        TrieNode {
-            next: std::collections::HashMap::new(),
-            val: None,
-        }
-    }
+const USAGE: &str =
+    "usage: day1 [--part 1|2] [--vocab VOCAB] [--zero] [--ignore-case MODE] [--normalize-digits] [--unicode-digits] [--match POLICY] [--numbers] [--reduce REDUCER] [--on-error POLICY] [--format FORMAT] [--explain | --quiet [--threads N]] [INPUT]
+       day1 audit [--vocab VOCAB] [--zero] [MATCHING OPTIONS] [--reduce REDUCER] [--quiet] [INPUT]
+
+Sums the calibration values of every line in INPUT (default ./input.txt, - for stdin)
+
+With audit, lists the lines of INPUT where digit words overlap like in eightwo, where
+part 1 and part 2 give different values, or where letting digits overlap changes the
+value, and counts the lines in each category. --quiet only prints the counts
 
-    // A convenience method for constructing a trie with the digits from one through nine included
-    fn with_digits() -> TrieNode {
-        let mut trie = TrieNode::new();
-        trie.insert("one", 1);
-        trie.insert("1", 1);
-        trie.insert("two", 2);
-        trie.insert("2", 2);
-        trie.insert("three", 3);
-        trie.insert("3", 3);
-        trie.insert("four", 4);
-        trie.insert("4", 4);
-        trie.insert("five", 5);
-        trie.insert("5", 5);
-        trie.insert("six", 6);
-        trie.insert("6", 6);
-        trie.insert("seven", 7);
-        trie.insert("7", 7);
-        trie.insert("eight", 8);
-        trie.insert("8", 8);
-        trie.insert("nine", 9);
-        trie.insert("9", 9);
-
-        trie
-    }
-
-    fn insert(&mut self, key: &str, val: u32) {
-        match key.chars().next() {
-            // There is more to insert, take the first character as the key and insert the rest of
-            // the string recursively as new trie nodes
-            Some(ch) => {
-                match self.next.get_mut(&ch) {
-                    // If we have this key already, we pass the rest of the string to the
-                    // corresponding TrieNode
-                    Some(node) => node.insert(&key[1..], val),
-                    None => {
-                        // Otherwise, we create a new TrieNode and insert it as a branch of our own
-                        // Node
-                        let mut new_node = TrieNode::new();
-                        new_node.insert(&key[1..], val);
-                        self.next.insert(ch, new_node);
-                    }
+options:
+    --part 1|2    only count numeric digits (1) or also spelled out ones (2, the default)
+    --vocab NAME|FILE
+                  count digits spelled out in this language instead of English: en (the
+                  default), de, fr or es, or the words in FILE given as lines of
+                  word = value
+    --zero        also count 0 and the word for zero as a digit; a vocabulary FILE
+                  must have a word with value 0 for this, which is ignored otherwise
+    --ignore-case ascii|unicode
+                  match words regardless of the case of ASCII letters, or of any letters
+                  by Unicode case folding, so STRASSE matches straße
+    --normalize-digits
+                  also count full-width, superscript, subscript, circled and other
+                  compatibility forms of digits as the digit itself
+    --unicode-digits
+                  also count the decimal digits of every script, like Arabic-Indic or
+                  Devanagari ones, as the digit with the same value
+    --match shortest|longest|all
+                  when a word of the vocabulary starts another one, like seven and
+                  seventeen, count only the shorter one (the default), only the longer
+                  one, or both
+    --numbers     take the first and last whole number of each line instead of digits,
+                  written as numerals or spelled out in English like sixteen or
+                  twenty-three, and join them into the calibration value, which is an
+                  error if it gets too large; can't be used with --vocab
+    --reduce first-last|sum|concat|max|min|first:K
+                  make the calibration value of a line from its first and last digit
+                  (the default), the sum of its digits, all of its digits one after
+                  the other, its largest or smallest digit, or its first K digits
+    --on-error strict|lenient|skip
+                  on a line without digits, stop with an error (strict), count it as 0
+                  (lenient, the default) or leave it out with a warning (skip)
+    --format text|json|csv
+                  print a log line per input line and the sum (text, the default), or a
+                  record per line with the digits found and where, plus a summary record
+                  with the sum and the number of errors, as JSON lines or CSV
+    -q, --quiet   only print the sum or summary, not every line checked
+    --explain     show how every line was decoded in the text format: its digits, highlighted
+                  in colour on a terminal, the nodes of the trie walked to find the first
+                  and last digit, backwards for a last digit read from the end of the line,
+                  and why they were chosen
+    --threads N   calibrate the input in N chunks side by side, at most as many as the
+                  machine has cores, only together with --quiet
+    -h, --help    print this message";
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    // The file to read, or "-" for stdin
+    input: String,
+    mode: CalibrationMode,
+    // A preset name or the path of a vocabulary file, None for the default English one
+    vocab: Option<String>,
+    // Whether zero counts as a digit
+    zero: bool,
+    options: MatchOptions,
+    reducer: Reducer,
+    policy: ErrorPolicy,
+    format: Format,
+    quiet: bool,
+    explain: bool,
+    threads: usize,
+    // Whether to audit the input instead of calibrating it
+    audit: bool,
+}
+
+// Parse the command line, not including the program name. Ok(None) means help was asked for
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Option<Args>, String> {
+    let mut args = args.peekable();
+    let audit = args.next_if(|arg| arg == "audit").is_some();
+    let mut input = None;
+    let mut mode = CalibrationMode::DigitsAndWords;
+    let mut vocab = None;
+    let mut zero = false;
+    let mut options = MatchOptions::default();
+    let mut reducer = Reducer::default();
+    let mut policy = ErrorPolicy::Lenient;
+    let mut format = Format::Text;
+    let mut quiet = false;
+    let mut explain = false;
+    let mut threads = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" => mode = args.next().ok_or("--part needs a value")?.parse()?,
+            "--vocab" => vocab = Some(args.next().ok_or("--vocab needs a value")?),
+            "--zero" => zero = true,
+            "--ignore-case" => match args.next().ok_or("--ignore-case needs a value")?.as_str() {
+                "ascii" => options.ascii_case_insensitive = true,
+                "unicode" => options.unicode_case_insensitive = true,
+                value => {
+                    return Err(format!(
+                        "invalid case mode {value:?}, expected ascii or unicode"
+                    ))
                 }
+            },
+            "--normalize-digits" => options.normalize_digits = true,
+            "--unicode-digits" => options.unicode_digits = true,
+            "--reduce" => reducer = args.next().ok_or("--reduce needs a value")?.parse()?,
+            "--numbers" => options.whole_numbers = true,
+            "--match" => options.policy = args.next().ok_or("--match needs a value")?.parse()?,
+            "--on-error" => policy = args.next().ok_or("--on-error needs a value")?.parse()?,
+            "--format" => format = args.next().ok_or("--format needs a value")?.parse()?,
+            "-q" | "--quiet" => quiet = true,
+            "--explain" => explain = true,
+            "--threads" => {
+                let value = args.next().ok_or("--threads needs a value")?;
+                threads = match value.parse() {
+                    Ok(threads) if threads > 0 => threads,
+                    _ => return Err(format!("invalid thread count {value:?}")),
+                };
             }
-            // We reached the end of the key string and now we can insert our value
-            None => self.val = Some(val),
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {arg:?}"))
+            }
+            _ if input.is_some() => return Err(format!("unexpected argument {arg:?}")),
+            _ => input = Some(arg),
         }
     }
 
-    // Return a digit if the the next characters spell the name of a digit, otherwise None. Also
-    // returns the number of characters read
-    fn get_digit(&self, chars: &mut std::str::Chars, read_count: u32) -> (Option<u32>, u32) {
-        self.val.map_or(
-            match chars.next() {
-                Some(ch) => {
-                    //println!("checking digit on {ch}");
+    if audit && (explain || threads > 1 || format != Format::Text) {
+        return Err(String::from(
+            "audit only writes text, and can't be used with --explain or --threads",
+        ));
+    }
+    if explain && (quiet || format != Format::Text) {
+        return Err(String::from(
+            "--explain only works with the text format and without --quiet",
+        ));
+    }
+    if options.whole_numbers && vocab.is_some() {
+        return Err(String::from(
+            "--numbers only reads English number words, so it can't be used with --vocab",
+        ));
+    }
 
-                    self.next
-                        .get(&ch)
-                        .map_or_else(|| (None, 0), |child| child.get_digit(chars, read_count + 1))
-                }
-                None => (None, 0),
-            },
-            |val| (Some(val), read_count),
-        )
+    // Lines are calibrated out of order with more than one thread, so there is only a summary
+    if threads > 1 && !quiet {
+        return Err(String::from(
+            "--threads can only be used together with --quiet",
+        ));
     }
+
+    Ok(Some(Args {
+        input: input.unwrap_or_else(|| String::from("./input.txt")),
+        mode,
+        vocab,
+        zero,
+        options,
+        reducer,
+        policy,
+        format,
+        quiet,
+        explain,
+        threads,
+        audit,
+    }))
 }
 
-fn get_until_digit(trie: &TrieNode, chars: &mut std::str::Chars) -> Option<u32> {
-    loop {
-        let (digit, read_count) = trie.get_digit(&mut chars.clone(), 0);
-        match digit {
-            Some(digit) => {
-                // advance position past what we just read
-                for _ in 0..read_count {
-                    chars.next();
-                }
-                break Some(digit);
-            }
-            _ => {
-                // Advance by one character and if we're at the end of the string return
-                if chars.next().is_none() {
-                    break None;
-                }
-            }
+fn open_input(input: &str) -> io::Result<Box<dyn BufRead>> {
+    if input == "-" {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(input)?)))
+    }
+}
+
+// Load one of the built-in vocabularies by name, or otherwise from a file. With zero, the
+// vocabulary has to include a word for zero, and without it any words for zero are left out
+fn load_vocabulary(vocab: &str, zero: bool) -> Result<Vocabulary, String> {
+    let preset = if zero {
+        Vocabulary::preset_with_zero(vocab)
+    } else {
+        Vocabulary::preset(vocab)
+    };
+    if let Some(preset) = preset {
+        return Ok(preset);
+    }
+    let text = fs::read_to_string(vocab)
+        .map_err(|err| format!("failed to read vocabulary {vocab}: {err}"))?;
+    let mut vocabulary = Vocabulary::parse(&text).map_err(|err| format!("{vocab}: {err}"))?;
+    if !zero {
+        vocabulary.words.retain(|&(_, val)| val != 0);
+    } else if !vocabulary.words.iter().any(|&(_, val)| val == 0) {
+        return Err(format!("{vocab}: --zero needs a word with value 0"));
+    }
+    Ok(vocabulary)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("day1: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let input = match open_input(&args.input) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("day1: failed to read {}: {err}", args.input);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Highlight explanations in colour, unless they go to a file or pipe or colour is turned off
+    // the usual way
+    let colour = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let result = if args.audit {
+        audit(&args, input, io::stdout().lock())
+    } else {
+        run(&args, input, io::stdout().lock(), colour)
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("day1: {err}");
+            ExitCode::FAILURE
         }
     }
 }
 
-fn calibrate(trie: &TrieNode, line: &str) -> u32 {
-    let mut chars = line.chars();
-    let first = get_until_digit(trie, &mut chars).unwrap_or(0);
-    //println!("calibrate(): got first digit {first}");
-    let mut last = first;
-    while let Some(digit) = get_until_digit(trie, &mut chars) {
-        //println!("calibrate(): got some digit {digit}");
-        last = digit;
+// Calibrate every line of the input and write the report to out. Lines are read and reported one
+// at a time, apart from with more than one thread where the whole input is split up up front
+fn run<R: BufRead, W: Write>(
+    args: &Args,
+    mut input: R,
+    out: W,
+    colour: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let read_error = |err: io::Error| format!("failed to read {}: {err}", args.input);
+    let matcher = build_matcher(args)?;
+
+    let mut report = Report::new(args.format, out)?;
+    if args.threads > 1 {
+        let mut contents = Vec::new();
+        input.read_to_end(&mut contents).map_err(read_error)?;
+        let summary =
+            sum_calibrations_parallel(&matcher, &contents, args.mode, args.policy, args.threads)?;
+        if args.policy == ErrorPolicy::Skip && summary.errors > 0 {
+            eprintln!("day1: warning: skipped {} lines", summary.errors);
+        }
+        warn_lenient(args.policy, &summary);
+        report.summary(&summary)?;
+        return Ok(());
     }
 
-    first * 10 + last
+    let mut lines = LineReader::new(input);
+    let mut summary = Summary::default();
+    while let Some((line_no, line)) = lines.next_line().map_err(read_error)? {
+        let result = locate_digits_bytes(&matcher, line, args.mode);
+        if let Err(err) = &result {
+            if args.policy == ErrorPolicy::Skip {
+                eprintln!("day1: warning: skipping line {line_no}: {err}");
+            }
+        }
+        let value = summary.add(
+            line_no,
+            result
+                .as_ref()
+                .map(Calibration::value)
+                .map_err(Clone::clone),
+            args.policy,
+        )?;
+        // A line that isn't valid UTF-8 can't be explained, but its record says as much
+        let text = std::str::from_utf8(line).ok().filter(|_| args.explain);
+        if let Some(text) = text {
+            report.explain(line_no, &matcher.explain(text, args.mode), colour)?;
+        } else if !args.quiet {
+            report.line(&LineRecord {
+                line_no,
+                text: &String::from_utf8_lossy(line),
+                result: &result,
+                value,
+                sum: summary.sum,
+            })?;
+        }
+    }
+
+    warn_lenient(args.policy, &summary);
+    report.summary(&summary)?;
+    Ok(())
 }
 
-fn main() {
-    let inpfile = "./input.txt";
+// Build the matcher for the vocabulary and options given on the command line
+fn build_matcher(args: &Args) -> Result<DigitMatcher, String> {
+    let trie = match (&args.vocab, args.zero) {
+        (None, false) => Trie::with_digits(),
+        (vocab, zero) => {
+            let vocab = vocab.as_deref().unwrap_or("en");
+            Trie::with_vocabulary(&load_vocabulary(vocab, zero)?)
+        }
+    };
+    Ok(
+        DigitMatcher::with_options(&trie, MatcherKind::default(), args.options)
+            .with_reducer(args.reducer),
+    )
+}
 
-    let file_contents = fs::read_to_string(inpfile).expect("Failed to read input file!");
+// Audit every line of the input and write the ambiguous ones, unless quiet, followed by the
+// number of lines in each category
+fn audit<R: BufRead, W: Write>(
+    args: &Args,
+    input: R,
+    mut out: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let read_error = |err: io::Error| format!("failed to read {}: {err}", args.input);
+    let matcher = build_matcher(args)?;
 
-    let trie = TrieNode::with_digits();
+    let mut lines = LineReader::new(input);
+    let mut summary = AuditSummary::default();
+    while let Some((line_no, line)) = lines.next_line().map_err(read_error)? {
+        let Ok(line) = std::str::from_utf8(line) else {
+            eprintln!("day1: warning: skipping line {line_no}: invalid UTF-8");
+            summary.lines += 1;
+            continue;
+        };
+        let audit = matcher.audit(line);
+        summary.add(&audit);
+        if !args.quiet && audit.is_ambiguous() {
+            write_audit(&mut out, line_no, line, &audit)?;
+        }
+    }
+
+    writeln!(out, "lines: {}", summary.lines)?;
+    writeln!(out, "overlapping words: {}", summary.overlapping)?;
+    writeln!(out, "part 1 and part 2 differ: {}", summary.parts_differ)?;
+    writeln!(
+        out,
+        "overlap changes the result: {}",
+        summary.overlap_changes_result
+    )?;
+    Ok(())
+}
 
-    let mut sum = 0;
-    let mut line_no = 0;
-    for line in file_contents.lines() {
-        let total = calibrate(&trie, line);
-        sum += total;
-        println!("checking line {line_no}: {line} total={total} sum={sum}");
-        line_no += 1;
+// Write an ambiguous line with a line of detail for each category it falls in
+fn write_audit<W: Write>(
+    out: &mut W,
+    line_no: usize,
+    line: &str,
+    audit: &LineAudit,
+) -> io::Result<()> {
+    let show = |result: &Result<u32, _>| match result {
+        Ok(value) => value.to_string(),
+        Err(err) => format!("{err}"),
+    };
+    writeln!(out, "line {line_no}: {line}")?;
+    if audit.has_overlaps() {
+        let pairs: Vec<String> = audit
+            .overlaps
+            .iter()
+            .map(|(a, b)| {
+                let word = |m: &day1::Match<u32>| &line[m.start..m.start + m.len];
+                format!("{}/{}", word(a), word(b))
+            })
+            .collect();
+        writeln!(out, "  overlapping words: {}", pairs.join(", "))?;
+    }
+    if audit.parts_differ() {
+        writeln!(
+            out,
+            "  part 1: {}, part 2: {}",
+            show(&audit.part1),
+            show(&audit.part2)
+        )?;
     }
+    if audit.overlap_changes_result() {
+        writeln!(
+            out,
+            "  overlap changes the result: {}, without overlaps {}",
+            show(&audit.part2),
+            show(&audit.without_overlaps)
+        )?;
+    }
+    Ok(())
+}
 
-    println!("{sum}");
+// Lines counted as 0 for lack of digits add the same to the sum as lines that really calibrate to
+// 0, so say how many there were
+fn warn_lenient(policy: ErrorPolicy, summary: &Summary) {
+    if policy == ErrorPolicy::Lenient && summary.errors > 0 {
+        eprintln!(
+            "day1: warning: counted {} lines without a calibration value as 0",
+            summary.errors
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use day1::{MatchPolicy, CSV_HEADER};
+
+    fn run_report(format: Format, policy: ErrorPolicy, input: &[u8]) -> String {
+        let args = Args {
+            input: String::from("-"),
+            mode: CalibrationMode::DigitsAndWords,
+            vocab: None,
+            zero: false,
+            options: MatchOptions::default(),
+            reducer: Reducer::default(),
+            policy,
+            format,
+            quiet: false,
+            explain: false,
+            threads: 1,
+            audit: false,
+        };
+        let mut out = Vec::new();
+        run(&args, input, &mut out, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_concat_sum() {
+        // Each line is worth 999999999, so the sum only fits a u64
+        let input = "999999999\n".repeat(5);
+        for threads in ["1", "2"] {
+            let args = ["-q", "--reduce", "concat", "--threads", threads, "-"];
+            let args = parse_args(args.into_iter().map(String::from))
+                .unwrap()
+                .unwrap();
+            let mut out = Vec::new();
+            run(&args, input.as_bytes(), &mut out, false).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), "4999999995\n");
+        }
+    }
+
+    #[test]
+    fn test_vocabulary_file_zero() {
+        let path = std::env::temp_dir().join(format!("day1-vocab-{}.txt", std::process::id()));
+        fs::write(&path, "nul = 0\neen = 1\n").unwrap();
+        let path = path.to_str().unwrap();
+        // Without --zero neither the word for zero nor the numeral 0 counts
+        for (zero, sum) in [(None, "22\n"), (Some("--zero"), "2\n")] {
+            let args = ["-q", "--vocab", path].into_iter().chain(zero).chain(["-"]);
+            let args = parse_args(args.map(String::from)).unwrap().unwrap();
+            let mut out = Vec::new();
+            run(&args, &b"0een\nnul1\n"[..], &mut out, false).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), sum, "{zero:?}");
+        }
+        fs::remove_file(path).unwrap();
+    }
 
     #[test]
-    fn test_calibrate() {
-        let trie = TrieNode::with_digits();
-        assert_eq!(calibrate(&trie, "1abc2"), 12);
-        assert_eq!(calibrate(&trie, "pqr3stu8vwx"), 38);
-        assert_eq!(calibrate(&trie, "a1b2c3d4e5f"), 15);
-        assert_eq!(calibrate(&trie, "treb7uchet"), 77);
-        assert_eq!(calibrate(&trie, "treb7uchet"), 77);
-        assert_eq!(calibrate(&trie, "two1nine"), 29);
-        assert_eq!(calibrate(&trie, "eightwothree"), 83);
-        assert_eq!(calibrate(&trie, "abcone2threexyz"), 13);
-        assert_eq!(calibrate(&trie, "xtwone3four"), 24);
-        assert_eq!(calibrate(&trie, "zoneight234"), 14);
-        assert_eq!(calibrate(&trie, "7pqrstsixteen"), 76);
-        assert_eq!(calibrate(&trie, "4nineeightseven2"), 42);
-        assert_eq!(calibrate(&trie, "53sdthreeninexrfone"), 51);
-        assert_eq!(calibrate(&trie, "threseven9"), 79);
-        assert_eq!(calibrate(&trie, "2hreseven98"), 28);
-        assert_eq!(calibrate(&trie, "thresevennin"), 77);
-        assert_eq!(calibrate(&trie, "hwqesaasd"), 0);
-        assert_eq!(calibrate(&trie, "fjdsgcsqppzdthreefour3one3lvmpm"), 33);
+    fn test_numbers_zero() {
+        for (zero, sum) in [(None, "110\n"), (Some("--zero"), "10\n")] {
+            let args = ["-q", "--numbers"].into_iter().chain(zero).chain(["-"]);
+            let args = parse_args(args.map(String::from)).unwrap().unwrap();
+            let mut out = Vec::new();
+            run(&args, &b"0abc5\nzero\n0\nzero5\n"[..], &mut out, false).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), sum, "{zero:?}");
+        }
     }
 
     #[test]
-    fn test_trie() {
-        let mut trie = TrieNode::new();
-        assert_eq!(trie.get_digit(&mut "".chars(), 0), (None, 0));
+    fn test_report() {
+        let input = b"two1nine\nx\"y\n7,six";
+        assert_eq!(
+            run_report(Format::Text, ErrorPolicy::Lenient, input),
+            "checking line 1: two1nine total=29 sum=29\n\
+             checking line 2: x\"y total=0 sum=29 error=no digit found\n\
+             checking line 3: 7,six total=76 sum=105\n\
+             105\n"
+        );
+        assert_eq!(
+            run_report(Format::Json, ErrorPolicy::Skip, input),
+            "{\"kind\":\"line\",\"line\":1,\"text\":\"two1nine\",\
+             \"first\":{\"digit\":2,\"start\":0,\"len\":3},\
+             \"last\":{\"digit\":9,\"start\":4,\"len\":4},\"value\":29,\"sum\":29,\"error\":null}\n\
+             {\"kind\":\"line\",\"line\":2,\"text\":\"x\\\"y\",\"first\":null,\"last\":null,\
+             \"value\":null,\"sum\":29,\"error\":\"no digit found\"}\n\
+             {\"kind\":\"line\",\"line\":3,\"text\":\"7,six\",\
+             \"first\":{\"digit\":7,\"start\":0,\"len\":1},\
+             \"last\":{\"digit\":6,\"start\":2,\"len\":3},\"value\":76,\"sum\":105,\"error\":null}\n\
+             {\"kind\":\"summary\",\"lines\":3,\"sum\":105,\"errors\":1}\n"
+        );
+        assert_eq!(
+            run_report(Format::Csv, ErrorPolicy::Lenient, input),
+            format!(
+                "{CSV_HEADER}\n\
+                 line,1,two1nine,2,0,3,9,4,4,29,29,\n\
+                 line,2,\"x\"\"y\",,,,,,,0,29,no digit found\n\
+                 line,3,\"7,six\",7,0,1,6,2,3,76,105,\n\
+                 summary,3,,,,,,,,,105,1\n"
+            )
+        );
+    }
 
-        trie.insert("seven", 7);
-        trie.insert("nine", 9);
+    #[test]
+    fn test_explain() {
+        let args = Args {
+            explain: true,
+            ..parse_args(std::iter::empty()).unwrap().unwrap()
+        };
+        let mut out = Vec::new();
+        run(&args, &b"7x\n\xff\n"[..], &mut out, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "line 1:\n  7x\n  F\n  digits: 7 = 7 at 0..1\n\
+             \x20 first: 7 = 7 at 0..1, the only digit, so both first and last, trie path 7=7\n\
+             \x20 last: 7 = 7 at 0..1, the only digit, so both first and last, trie path 7=7\n\
+             \x20 value: 77, first-last of 7\n\
+             checking line 2: \u{fffd} total=0 sum=77 error=invalid UTF-8: \
+             invalid utf-8 sequence of 1 bytes from index 0\n\
+             77\n"
+        );
+    }
 
-        assert_eq!(trie.get_digit(&mut "seven".chars(), 0), (Some(7), 5));
+    #[test]
+    fn test_audit() {
+        let args = parse_args(["audit", "-"].into_iter().map(String::from))
+            .unwrap()
+            .unwrap();
+        let mut out = Vec::new();
+        audit(&args, &b"1eightwo\na1b2\nxtwone3four\n"[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "line 1: 1eightwo\n\
+             \x20 overlapping words: eight/two\n\
+             \x20 part 1: 11, part 2: 12\n\
+             \x20 overlap changes the result: 12, without overlaps 18\n\
+             line 3: xtwone3four\n\
+             \x20 overlapping words: two/one\n\
+             \x20 part 1: 33, part 2: 24\n\
+             lines: 3\n\
+             overlapping words: 2\n\
+             part 1 and part 2 differ: 2\n\
+             overlap changes the result: 1\n"
+        );
+    }
 
-        trie.insert("7", 7);
-        assert_eq!(trie.get_digit(&mut "7".chars(), 0), (Some(7), 1));
+    #[test]
+    fn test_parse_args() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse(&[]),
+            Ok(Some(Args {
+                input: String::from("./input.txt"),
+                mode: CalibrationMode::DigitsAndWords,
+                vocab: None,
+                zero: false,
+                options: MatchOptions::default(),
+                reducer: Reducer::default(),
+                policy: ErrorPolicy::Lenient,
+                format: Format::Text,
+                quiet: false,
+                explain: false,
+                threads: 1,
+                audit: false,
+            }))
+        );
+        assert_eq!(
+            parse(&[
+                "--part",
+                "1",
+                "-q",
+                "--on-error",
+                "skip",
+                "--format",
+                "csv",
+                "-"
+            ]),
+            Ok(Some(Args {
+                input: String::from("-"),
+                mode: CalibrationMode::Digits,
+                vocab: None,
+                zero: false,
+                options: MatchOptions::default(),
+                reducer: Reducer::default(),
+                policy: ErrorPolicy::Skip,
+                format: Format::Csv,
+                quiet: true,
+                explain: false,
+                threads: 1,
+                audit: false,
+            }))
+        );
+        assert_eq!(
+            parse(&["other.txt", "--part", "2"]).map(|args| args.unwrap().input),
+            Ok(String::from("other.txt"))
+        );
+        assert_eq!(parse(&["--quiet", "--help"]), Ok(None));
+        assert!(parse(&["--part", "3"]).is_err());
+        assert!(parse(&["--part"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--on-error", "ignore"]).is_err());
+        assert!(parse(&["--format", "xml"]).is_err());
+        assert_eq!(
+            parse(&["-q", "--threads", "4"]).map(|args| args.unwrap().threads),
+            Ok(4)
+        );
+        assert!(parse(&["--threads", "4"]).is_err());
+        assert_eq!(
+            parse(&["--vocab", "de"]).map(|args| args.unwrap().vocab),
+            Ok(Some(String::from("de")))
+        );
+        assert!(parse(&["--vocab"]).is_err());
+        assert_eq!(parse(&["--zero"]).map(|args| args.unwrap().zero), Ok(true));
+        assert_eq!(
+            parse(&["--explain"]).map(|args| args.unwrap().explain),
+            Ok(true)
+        );
+        assert!(parse(&["--explain", "-q"]).is_err());
+        assert!(parse(&["--explain", "--format", "json"]).is_err());
+        assert_eq!(
+            parse(&["audit", "-q"]).map(|args| args.unwrap().audit),
+            Ok(true)
+        );
+        assert_eq!(
+            parse(&["audit"]).map(|args| args.unwrap().input),
+            Ok(String::from("./input.txt"))
+        );
+        assert_eq!(
+            parse(&["-q", "audit"]).map(|args| args.unwrap().input),
+            Ok(String::from("audit"))
+        );
+        assert!(parse(&["audit", "--explain"]).is_err());
+        assert!(parse(&["audit", "--format", "csv"]).is_err());
+        assert!(parse(&["-q", "--threads", "0"]).is_err());
+        assert!(parse(&["a.txt", "b.txt"]).is_err());
+        assert_eq!(
+            parse(&["--ignore-case", "unicode", "--normalize-digits"])
+                .map(|args| args.unwrap().options),
+            Ok(MatchOptions {
+                unicode_case_insensitive: true,
+                normalize_digits: true,
+                ..MatchOptions::default()
+            })
+        );
+        assert!(parse(&["--ignore-case", "all"]).is_err());
+        assert_eq!(
+            parse(&["--unicode-digits"]).map(|args| args.unwrap().options.unicode_digits),
+            Ok(true)
+        );
+        assert_eq!(
+            parse(&["--match", "longest"]).map(|args| args.unwrap().options.policy),
+            Ok(MatchPolicy::Longest)
+        );
+        assert!(parse(&["--match", "first"]).is_err());
+        assert_eq!(
+            parse(&["--numbers"]).map(|args| args.unwrap().options.whole_numbers),
+            Ok(true)
+        );
+        assert!(parse(&["--numbers", "--vocab", "de"]).is_err());
+        assert_eq!(
+            parse(&["--reduce", "first:4"]).map(|args| args.unwrap().reducer),
+            Ok(Reducer::FirstK(4))
+        );
+        assert!(parse(&["--reduce", "avg"]).is_err());
     }
 }
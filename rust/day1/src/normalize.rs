@@ -0,0 +1,317 @@
+use crate::automaton::Match;
+use crate::trie::{MatchPolicy, Trie};
+
+/// How loosely to match the keys of a vocabulary against a line. Both the keys and the lines are
+/// folded the same way before matching, and matches are reported at their place in the original
+/// line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchOptions {
+    /// Match ASCII letters regardless of case, so "One" counts as "one"
+    pub ascii_case_insensitive: bool,
+    /// Match any letters regardless of case using Unicode full case folding, so "FÜNF" counts as
+    /// "fünf" and "STRASSE" as "straße"
+    pub unicode_case_insensitive: bool,
+    /// Treat compatibility forms of digits as the plain digit, the way NFKC normalisation does:
+    /// full-width digits like "１", superscripts and subscripts, circled digits and mathematical
+    /// digits
+    pub normalize_digits: bool,
+    /// Treat the decimal digits of every script, any char of the Unicode category Nd like the
+    /// Arabic-Indic "٣" or the Devanagari "७", as the plain digit with the same value
+    pub unicode_digits: bool,
+    /// Which keys count when several of them start at the same place in a line
+    pub policy: MatchPolicy,
+    /// Find whole numbers instead of digits, written as numerals or spelled out in English (see
+    /// [`NumberParser`](crate::NumberParser)), in place of the keys of the vocabulary
+    pub whole_numbers: bool,
+}
+
+impl MatchOptions {
+    /// Whether these options leave every char as it is
+    pub fn is_exact(&self) -> bool {
+        !(self.ascii_case_insensitive
+            || self.unicode_case_insensitive
+            || self.normalize_digits
+            || self.unicode_digits)
+    }
+
+    /// Fold a single char into `out`, which may take more than one char for some Unicode case
+    /// foldings
+    pub fn fold_char(&self, ch: char, out: &mut String) {
+        if self.unicode_digits {
+            if let Some(digit) = decimal_digit(ch) {
+                out.push(char::from(b'0' + digit));
+                return;
+            }
+        }
+        if self.normalize_digits {
+            if let Some(digit) = compatibility_digit(ch) {
+                out.push(char::from(b'0' + digit));
+                return;
+            }
+        }
+        if self.unicode_case_insensitive {
+            match FULL_FOLDINGS.binary_search_by_key(&ch, |&(from, _)| from) {
+                Ok(idx) => out.push_str(FULL_FOLDINGS[idx].1),
+                Err(_) => out.extend(ch.to_lowercase()),
+            }
+        } else if self.ascii_case_insensitive {
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+
+    pub fn fold_str(&self, text: &str) -> String {
+        let mut folded = String::with_capacity(text.len());
+        for ch in text.chars() {
+            self.fold_char(ch, &mut folded);
+        }
+        folded
+    }
+}
+
+// Letters whose full case folding differs from their lowercase mapping, sorted by char: the
+// sharp s, final sigma and other letter variants, and letters that fold into a base letter and
+// combining marks or into several letters. Greek letters with an iota subscript and Cherokee
+// letters are left to lowercasing
+const FULL_FOLDINGS: &[(char, &str)] = &[
+    ('µ', "μ"),
+    ('ß', "ss"),
+    ('ŉ', "\u{2bc}n"),
+    ('ſ', "s"),
+    ('\u{1f0}', "j\u{30c}"),
+    ('\u{345}', "ι"),
+    ('\u{390}', "ι\u{308}\u{301}"),
+    ('\u{3b0}', "υ\u{308}\u{301}"),
+    ('ς', "σ"),
+    ('ϐ', "β"),
+    ('ϑ', "θ"),
+    ('ϕ', "φ"),
+    ('ϖ', "π"),
+    ('ϰ', "κ"),
+    ('ϱ', "ρ"),
+    ('ϵ', "ε"),
+    ('\u{587}', "\u{565}\u{582}"),
+    ('\u{1e96}', "h\u{331}"),
+    ('\u{1e97}', "t\u{308}"),
+    ('\u{1e98}', "w\u{30a}"),
+    ('\u{1e99}', "y\u{30a}"),
+    ('\u{1e9a}', "a\u{2be}"),
+    ('\u{1e9b}', "\u{1e61}"),
+    ('ẞ', "ss"),
+    ('\u{1fbe}', "ι"),
+    ('ﬀ', "ff"),
+    ('ﬁ', "fi"),
+    ('ﬂ', "fl"),
+    ('ﬃ', "ffi"),
+    ('ﬄ', "ffl"),
+    ('ﬅ', "st"),
+    ('ﬆ', "st"),
+];
+
+// The first code point of each block of ten consecutive digits, zero to nine, that NFKC
+// normalises to the ASCII digits
+const COMPATIBILITY_ZEROS: &[u32] = &[
+    0x2080,  // subscript
+    0xff10,  // full-width
+    0x1d7ce, // mathematical bold
+    0x1d7d8, // mathematical double-struck
+    0x1d7e2, // mathematical sans-serif
+    0x1d7ec, // mathematical sans-serif bold
+    0x1d7f6, // mathematical monospace
+    0x1fbf0, // segmented
+];
+
+// The value of a compatibility form of a digit, or None if ch isn't one
+fn compatibility_digit(ch: char) -> Option<u8> {
+    let code = ch as u32;
+    if let Some(zero) = COMPATIBILITY_ZEROS
+        .iter()
+        .find(|&&zero| (zero..zero + 10).contains(&code))
+    {
+        return Some((code - zero) as u8);
+    }
+    match ch {
+        '⁰' => Some(0),
+        '¹' => Some(1),
+        '²' => Some(2),
+        '³' => Some(3),
+        '⁴'..='⁹' => Some((code - '⁴' as u32) as u8 + 4),
+        '⓪' => Some(0),
+        '①'..='⑨' => Some((code - '①' as u32) as u8 + 1),
+        _ => None,
+    }
+}
+
+// The zero of each run of ten decimal digits in Unicode 15.1, in order. Unicode guarantees that
+// every char of category Nd is part of such a run, from zero up to nine
+const DECIMAL_ZEROS: &[u32] = &[
+    0x0030, 0x0660, 0x06f0, 0x07c0, 0x0966, 0x09e6, 0x0a66, 0x0ae6, 0x0b66, 0x0be6, 0x0c66, 0x0ce6,
+    0x0d66, 0x0de6, 0x0e50, 0x0ed0, 0x0f20, 0x1040, 0x1090, 0x17e0, 0x1810, 0x1946, 0x19d0, 0x1a80,
+    0x1a90, 0x1b50, 0x1bb0, 0x1c40, 0x1c50, 0xa620, 0xa8d0, 0xa900, 0xa9d0, 0xa9f0, 0xaa50, 0xabf0,
+    0xff10, 0x104a0, 0x10d30, 0x11066, 0x110f0, 0x11136, 0x111d0, 0x112f0, 0x11450, 0x114d0,
+    0x11650, 0x116c0, 0x11730, 0x118e0, 0x11950, 0x11c50, 0x11d50, 0x11da0, 0x11f50, 0x16a60,
+    0x16ac0, 0x16b50, 0x1d7ce, 0x1d7d8, 0x1d7e2, 0x1d7ec, 0x1d7f6, 0x1e140, 0x1e2f0, 0x1e4f0,
+    0x1e950, 0x1fbf0,
+];
+
+// The value of a decimal digit of any script, or None if ch isn't one
+fn decimal_digit(ch: char) -> Option<u8> {
+    if ch.is_ascii() {
+        return ch.to_digit(10).map(|digit| digit as u8);
+    }
+    let code = ch as u32;
+    // The last run starting at or before ch is the only one that can hold it
+    let idx = DECIMAL_ZEROS.partition_point(|&zero| zero <= code);
+    let zero = DECIMAL_ZEROS[idx.checked_sub(1)?];
+    (code - zero < 10).then(|| (code - zero) as u8)
+}
+
+/// A line with its chars folded according to some [`MatchOptions`], remembering which char of
+/// the original line each byte of the folded text came from
+pub(crate) struct FoldedLine {
+    text: String,
+    // The byte range in the original line of the char each byte of the folded text came from
+    spans: Vec<(usize, usize)>,
+}
+
+impl FoldedLine {
+    pub fn new(line: &str, options: &MatchOptions) -> FoldedLine {
+        let mut text = String::with_capacity(line.len());
+        let mut spans = Vec::with_capacity(line.len());
+        for (start, ch) in line.char_indices() {
+            options.fold_char(ch, &mut text);
+            spans.resize(text.len(), (start, start + ch.len_utf8()));
+        }
+        FoldedLine { text, spans }
+    }
+
+    /// The folded text to match against
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Translate a match in the folded text to the chars of the original line it covers
+    pub fn to_original<V>(&self, m: Match<V>) -> Match<V> {
+        let start = self.spans[m.start].0;
+        let end = self.spans[m.start + m.len - 1].1;
+        Match {
+            value: m.value,
+            start,
+            len: end - start,
+        }
+    }
+}
+
+impl<V: Clone> Trie<char, V> {
+    /// Build a trie holding the same values under each key folded according to `options`, to
+    /// match lines folded the same way
+    pub fn folded(&self, options: &MatchOptions) -> Trie<char, V> {
+        let mut folded = Trie::new();
+        self.for_each_key(&mut Vec::new(), &mut |key, val| {
+            let key: String = key.iter().collect();
+            folded.insert(options.fold_str(&key).chars(), val.clone());
+        });
+        folded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        let ascii = MatchOptions {
+            ascii_case_insensitive: true,
+            ..MatchOptions::default()
+        };
+        let unicode = MatchOptions {
+            unicode_case_insensitive: true,
+            ..MatchOptions::default()
+        };
+        let digits = MatchOptions {
+            normalize_digits: true,
+            ..MatchOptions::default()
+        };
+        assert_eq!(ascii.fold_str("OneTWO FÜNF"), "onetwo fÜnf");
+        assert_eq!(unicode.fold_str("OneTWO FÜNF"), "onetwo fünf");
+        assert!(FULL_FOLDINGS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(unicode.fold_str("Straße STRASSE ẞ"), "strasse strasse ss");
+        assert_eq!(unicode.fold_str("ΣΊΣΥΦΟΣ σίσυφος"), "σίσυφοσ σίσυφοσ");
+        assert_eq!(unicode.fold_str("ﬁve"), "five");
+        assert_eq!(ascii.fold_str("Straße"), "straße");
+        assert_eq!(digits.fold_str("１²₃④𝟓𝟞x7"), "123456x7");
+        assert_eq!(digits.fold_str("ONE"), "ONE");
+        assert!(MatchOptions::default().is_exact());
+        assert!(MatchOptions {
+            policy: MatchPolicy::Longest,
+            ..MatchOptions::default()
+        }
+        .is_exact());
+    }
+
+    #[test]
+    fn test_decimal_digits() {
+        assert!(DECIMAL_ZEROS.windows(2).all(|pair| pair[1] - pair[0] >= 10));
+        for &zero in DECIMAL_ZEROS {
+            for digit in 0..10 {
+                let ch = char::from_u32(zero + digit).unwrap();
+                assert!(ch.is_numeric() && !ch.is_alphabetic(), "{ch:?}");
+                assert_eq!(decimal_digit(ch), Some(digit as u8), "{ch:?}");
+            }
+        }
+        assert_eq!(decimal_digit('x'), None);
+        assert_eq!(decimal_digit('\u{65f}'), None);
+        assert_eq!(decimal_digit('\u{66a}'), None);
+        // Numeric, but not a decimal digit
+        assert_eq!(decimal_digit('½'), None);
+        assert_eq!(decimal_digit('Ⅷ'), None);
+
+        let options = MatchOptions {
+            unicode_digits: true,
+            ..MatchOptions::default()
+        };
+        assert_eq!(options.fold_str("٣x७৯"), "3x79");
+        assert_eq!(options.fold_str("①"), "①");
+    }
+
+    #[test]
+    fn test_folded_line() {
+        let options = MatchOptions {
+            unicode_case_insensitive: true,
+            normalize_digits: true,
+            ..MatchOptions::default()
+        };
+        // "İ" lowercases to two chars, "i" and a combining dot
+        let line = FoldedLine::new("İx１TWO", &options);
+        assert_eq!(line.text(), "i\u{307}x1two");
+
+        let m = Match {
+            value: 1,
+            start: 4,
+            len: 1,
+        };
+        assert_eq!(
+            line.to_original(m),
+            Match {
+                value: 1,
+                start: 3,
+                len: 3
+            }
+        );
+        let m = Match {
+            value: 2,
+            start: 5,
+            len: 3,
+        };
+        assert_eq!(line.to_original(m).start, 6);
+        // A match on part of the folding of "İ" covers all of it
+        let m = Match {
+            value: 0,
+            start: 0,
+            len: 1,
+        };
+        assert_eq!(line.to_original(m).len, 2);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::automaton::Match;
+use crate::calibration::{CalibrationError, CalibrationMode};
+use crate::trie::{MatchPolicy, Trie};
+
+// The words numbers are spelled with, by what they contribute to the number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberWord {
+    // One through nine, which can stand alone, end a compound or count hundreds
+    Unit(u32),
+    // Ten through nineteen
+    Teen(u32),
+    // Twenty, thirty and so on, which a unit may follow
+    Tens(u32),
+    Hundred,
+    // As in "one hundred and five"
+    And,
+    // Only ever stands alone
+    Zero,
+}
+
+const UNITS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const TEENS: [&str; 10] = [
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Finds whole numbers in a line, written as numerals like "23" or spelled out in English like
+/// "seventeen", "twenty-three" or "three hundred and twelve". The words of a compound may be
+/// joined by a hyphen, a space or nothing at all, as in "twentythree". Zero, as "0" or "zero",
+/// only counts when asked for with [`NumberParser::with_zero`]
+#[derive(Debug)]
+pub struct NumberParser {
+    words: Trie<char, NumberWord>,
+    zero: bool,
+}
+
+impl NumberParser {
+    pub fn new() -> NumberParser {
+        let mut words = Trie::new();
+        for (val, word) in (1..).zip(UNITS) {
+            words.insert(word.chars(), NumberWord::Unit(val));
+        }
+        for (val, word) in (10..).zip(TEENS) {
+            words.insert(word.chars(), NumberWord::Teen(val));
+        }
+        for (val, word) in (2..).zip(TENS) {
+            words.insert(word.chars(), NumberWord::Tens(val * 10));
+        }
+        words.insert("hundred".chars(), NumberWord::Hundred);
+        words.insert("and".chars(), NumberWord::And);
+        NumberParser { words, zero: false }
+    }
+
+    /// A parser that also counts zero as a number
+    pub fn with_zero() -> NumberParser {
+        let mut parser = NumberParser::new();
+        parser.words.insert("zero".chars(), NumberWord::Zero);
+        parser.zero = true;
+        parser
+    }
+
+    /// Every whole number in the line, in order of where they start. A number lying within a
+    /// longer one, like the "three" of "twenty-three", is left out, but numbers that merely
+    /// overlap, like in "eightwo", both count. In [`CalibrationMode::Digits`] only numerals count
+    pub fn numbers(&self, line: &str, mode: CalibrationMode) -> Vec<Match<u32>> {
+        let words = mode == CalibrationMode::DigitsAndWords;
+        let mut numbers = Vec::new();
+        let mut max_end = 0;
+        let mut prev = None;
+        for (start, ch) in line.char_indices() {
+            // A run of numerals is only ever read as a whole
+            let inside_numeral =
+                ch.is_ascii_digit() && prev.is_some_and(|prev: char| prev.is_ascii_digit());
+            prev = Some(ch);
+            if inside_numeral {
+                continue;
+            }
+            if let Some((value, len)) = self.parse(&line[start..], words) {
+                if start + len > max_end {
+                    max_end = start + len;
+                    numbers.push(Match { value, start, len });
+                }
+            }
+        }
+        numbers
+    }
+
+    /// Check that every numeral in the line fits a `u32`, as [`numbers`](NumberParser::numbers)
+    /// leaves out any that doesn't
+    pub fn check_numerals(&self, line: &str) -> Result<(), CalibrationError> {
+        let numerals = line.split(|ch: char| !ch.is_ascii_digit());
+        match numerals
+            .filter(|numeral| !numeral.is_empty())
+            .all(|numeral| numeral.parse::<u32>().is_ok())
+        {
+            true => Ok(()),
+            false => Err(CalibrationError::Overflow),
+        }
+    }
+
+    // The value and length in bytes of the longest number at the start of text
+    fn parse(&self, text: &str, words: bool) -> Option<(u32, usize)> {
+        let numeral_len = text.bytes().take_while(u8::is_ascii_digit).count();
+        if numeral_len > 0 {
+            let value = text[..numeral_len].parse().ok()?;
+            return (value > 0 || self.zero).then_some((value, numeral_len));
+        }
+        if !words {
+            return None;
+        }
+
+        let (word, len) = self.word(text)?;
+        match word {
+            NumberWord::Unit(units) => match self.hundreds(&text[len..]) {
+                Some(hundred_len) => {
+                    let len = len + hundred_len;
+                    let (rest, rest_len) = self.after_hundred(&text[len..]).unwrap_or((0, 0));
+                    Some((units * 100 + rest, len + rest_len))
+                }
+                None => Some((units, len)),
+            },
+            NumberWord::Teen(_) | NumberWord::Tens(_) => self.below_hundred(text),
+            NumberWord::Zero => Some((0, len)),
+            NumberWord::Hundred | NumberWord::And => None,
+        }
+    }
+
+    // The value and length of a number from one to ninety-nine at the start of text
+    fn below_hundred(&self, text: &str) -> Option<(u32, usize)> {
+        match self.word(text)? {
+            (NumberWord::Unit(val) | NumberWord::Teen(val), len) => Some((val, len)),
+            (NumberWord::Tens(tens), len) => {
+                let sep = separator(&text[len..]);
+                match self.word(&text[len + sep..]) {
+                    Some((NumberWord::Unit(units), units_len)) => {
+                        Some((tens + units, len + sep + units_len))
+                    }
+                    _ => Some((tens, len)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // The length of "hundred" at the start of text, after an optional separator
+    fn hundreds(&self, text: &str) -> Option<usize> {
+        let sep = separator(text);
+        match self.word(&text[sep..])? {
+            (NumberWord::Hundred, len) => Some(sep + len),
+            _ => None,
+        }
+    }
+
+    // The value and length of what follows "hundred" in a number, like the " and twelve" of
+    // "three hundred and twelve"
+    fn after_hundred(&self, text: &str) -> Option<(u32, usize)> {
+        let mut len = separator(text);
+        if let Some((NumberWord::And, and_len)) = self.word(&text[len..]) {
+            len += and_len;
+            len += separator(&text[len..]);
+        }
+        let (val, rest_len) = self.below_hundred(&text[len..])?;
+        Some((val, len + rest_len))
+    }
+
+    // The longest number word at the start of text and its length in bytes
+    fn word(&self, text: &str) -> Option<(NumberWord, usize)> {
+        let (word, char_count) = self
+            .words
+            .get_matches(&mut text.chars(), MatchPolicy::Longest)
+            .pop()?;
+        let len = text
+            .char_indices()
+            .nth(char_count as usize)
+            .map_or(text.len(), |(idx, _)| idx);
+        Some((word, len))
+    }
+}
+
+impl Default for NumberParser {
+    fn default() -> NumberParser {
+        NumberParser::new()
+    }
+}
+
+// The length of the hyphen or space that may join the words of a number at the start of text
+fn separator(text: &str) -> usize {
+    match text.as_bytes().first() {
+        Some(b'-' | b' ') => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(line: &str) -> Vec<u32> {
+        NumberParser::new()
+            .numbers(line, CalibrationMode::DigitsAndWords)
+            .into_iter()
+            .map(|m| m.value)
+            .collect()
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(numbers("sixteen"), [16]);
+        assert_eq!(numbers("xtwenty-threey"), [23]);
+        assert_eq!(numbers("twentythree and ninety nine"), [23, 99]);
+        assert_eq!(numbers("forty-"), [40]);
+        assert_eq!(numbers("seventy-twelve"), [70, 12]);
+        assert_eq!(numbers("eightwo"), [8, 2]);
+        assert_eq!(numbers("nineteenine"), [19, 9]);
+        assert_eq!(numbers("three hundred and twelve"), [312]);
+        assert_eq!(numbers("onehundred-five"), [105]);
+        assert_eq!(numbers("nine hundred ninety-nine"), [999]);
+        assert_eq!(numbers("two hundred and x"), [200]);
+        assert_eq!(numbers("hundred and ten"), [10]);
+        assert_eq!(numbers("a12b345c6789d"), [12, 345, 6789]);
+        assert_eq!(numbers("seven7seventeen"), [7, 7, 17]);
+        assert!(numbers("hundred and").is_empty());
+        assert_eq!(numbers("x0004294967295"), [4294967295]);
+
+        let parser = NumberParser::new();
+        let found = parser.numbers("€twenty-one 42", CalibrationMode::Digits);
+        assert_eq!(
+            found,
+            [Match {
+                value: 42,
+                start: 14,
+                len: 2
+            }]
+        );
+        let found = parser.numbers("€twenty-one 42", CalibrationMode::DigitsAndWords);
+        assert_eq!((found[0].start, found[0].len), (3, 10));
+    }
+
+    #[test]
+    fn test_zero() {
+        assert_eq!(numbers("0abc5 zero 00"), [5]);
+        assert_eq!(numbers("10 05"), [10, 5]);
+
+        let parser = NumberParser::with_zero();
+        let found: Vec<u32> = parser
+            .numbers("0abc5 zero 00 twozero", CalibrationMode::DigitsAndWords)
+            .into_iter()
+            .map(|m| m.value)
+            .collect();
+        assert_eq!(found, [0, 5, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_check_numerals() {
+        let parser = NumberParser::new();
+        assert_eq!(parser.check_numerals("a12b345c6789d"), Ok(()));
+        assert_eq!(parser.check_numerals("seven 4294967295"), Ok(()));
+        assert_eq!(
+            parser.check_numerals("1 4294967296"),
+            Err(CalibrationError::Overflow)
+        );
+    }
+}
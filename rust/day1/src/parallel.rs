@@ -0,0 +1,194 @@
+use crate::calibration::{
+    lines, locate_digits_bytes, sum_calibrations, CalibrationMode, DigitMatcher, ErrorPolicy,
+    LineError, Summary,
+};
+
+/// The same as [`sum_calibrations`], but splitting the input on line boundaries into a chunk per
+/// thread and calibrating the chunks side by side. The totals, and under
+/// [`ErrorPolicy::Strict`] which line fails, come out exactly as they would from a serial run.
+/// No more threads are started than the machine can run at once, however many are asked for
+pub fn sum_calibrations_parallel(
+    matcher: &DigitMatcher,
+    input: &[u8],
+    mode: CalibrationMode,
+    policy: ErrorPolicy,
+    threads: usize,
+) -> Result<Summary, LineError> {
+    let cores = std::thread::available_parallelism().map_or(1, usize::from);
+    let chunks = split_lines(input, threads.min(cores));
+    let results: Vec<Result<Summary, LineError>> = std::thread::scope(|scope| {
+        let workers: Vec<_> = chunks
+            .iter()
+            .map(|chunk| scope.spawn(move || sum_calibrations(matcher, chunk, mode, policy)))
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("calibration worker panicked"))
+            .collect()
+    });
+
+    // Combine the chunks in input order. Every chunk numbered its lines from 1, so shift them by
+    // the lines in the chunks before it, which have all succeeded if we get to an error
+    let mut total = Summary::default();
+    for (chunk, result) in chunks.iter().zip(results) {
+        match result {
+            Ok(summary) => {
+                let Some(sum) = total.sum.checked_add(summary.sum) else {
+                    return Err(find_overflow(matcher, chunk, mode, policy, total));
+                };
+                total.lines += summary.lines;
+                total.sum = sum;
+                total.errors += summary.errors;
+            }
+            Err(err) => {
+                return Err(LineError {
+                    line_no: total.lines + err.line_no,
+                    ..err
+                })
+            }
+        }
+    }
+    Ok(total)
+}
+
+// The chunks before this one add up to total, and adding this chunk on top overflows the sum. Go
+// through the chunk again line by line starting from that total, to find the same line a serial
+// run would have stopped at
+fn find_overflow(
+    matcher: &DigitMatcher,
+    chunk: &[u8],
+    mode: CalibrationMode,
+    policy: ErrorPolicy,
+    mut total: Summary,
+) -> LineError {
+    for (line_no, line) in (total.lines + 1..).zip(lines(chunk)) {
+        let result = locate_digits_bytes(matcher, line, mode).map(|c| c.value());
+        if let Err(err) = total.add(line_no, result, policy) {
+            return err;
+        }
+    }
+    unreachable!("adding up the chunk overflowed before")
+}
+
+// Split the input into at most `parts` chunks of about the same size, each ending just after a
+// newline (apart from the last one) so that no line is cut in two
+fn split_lines(input: &[u8], parts: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = input;
+    for part in (1..=parts.max(1)).rev() {
+        if rest.is_empty() {
+            break;
+        }
+        let target = rest.len().div_ceil(part);
+        let end = match rest[target - 1..].iter().position(|&b| b == b'\n') {
+            Some(newline) => target + newline,
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::CalibrationError;
+    use crate::reduce::Reducer;
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(
+            split_lines(b"one\ntwo\nsix\nten\n", 2),
+            vec![&b"one\ntwo\n"[..], b"six\nten\n"]
+        );
+        assert_eq!(
+            split_lines(b"one\ntwo\nthree\nfour\n", 2),
+            vec![&b"one\ntwo\nthree\n"[..], b"four\n"]
+        );
+        assert_eq!(split_lines(b"a\nb\nc", 3), vec![&b"a\n"[..], b"b\n", b"c"]);
+        assert_eq!(split_lines(b"a\nb\n", 8), vec![&b"a\n"[..], b"b\n"]);
+        assert_eq!(split_lines(b"one line", 4), vec![&b"one line"[..]]);
+        assert!(split_lines(b"", 4).is_empty());
+        assert_eq!(split_lines(b"a\nb", usize::MAX), vec![&b"a\n"[..], b"b"]);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let input = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+        let matcher = DigitMatcher::default();
+        for mode in [CalibrationMode::Digits, CalibrationMode::DigitsAndWords] {
+            let serial = sum_calibrations(&matcher, &input, mode, ErrorPolicy::Strict);
+            for threads in [1, 2, 3, 7, 16, 2000] {
+                let parallel =
+                    sum_calibrations_parallel(&matcher, &input, mode, ErrorPolicy::Strict, threads);
+                assert_eq!(parallel, serial, "{threads} threads");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_sum_overflow() {
+        // Every line is worth 999999999, so any two chunks together exceed u32::MAX
+        let input = "999999999\n".repeat(8);
+        let matcher = DigitMatcher::default().with_reducer(Reducer::Concat);
+        for threads in [1, 2, 3, 8] {
+            let summary = sum_calibrations_parallel(
+                &matcher,
+                input.as_bytes(),
+                CalibrationMode::Digits,
+                ErrorPolicy::Strict,
+                threads,
+            );
+            assert_eq!(summary.map(|summary| summary.sum), Ok(7_999_999_992));
+        }
+
+        let total = Summary {
+            lines: 4,
+            sum: u64::MAX - 1_500_000_000,
+            errors: 0,
+        };
+        let err = find_overflow(
+            &matcher,
+            b"1\n999999999\n999999999\n999999999\n",
+            CalibrationMode::Digits,
+            ErrorPolicy::Strict,
+            total,
+        );
+        assert_eq!(
+            err,
+            LineError {
+                line_no: 7,
+                error: CalibrationError::SumOverflow
+            }
+        );
+    }
+
+    #[test]
+    fn test_parallel_many_threads() {
+        let input = b"1\ntwo\n3four\n";
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::DigitsAndWords;
+        let serial = sum_calibrations(&matcher, input, mode, ErrorPolicy::Strict);
+        for threads in [1_000_000_000_000, usize::MAX] {
+            let parallel =
+                sum_calibrations_parallel(&matcher, input, mode, ErrorPolicy::Strict, threads);
+            assert_eq!(parallel, serial, "{threads} threads");
+        }
+    }
+
+    #[test]
+    fn test_parallel_errors() {
+        let input = b"1\ntwo\nx\n\n3four\nnone\n5";
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::DigitsAndWords;
+        for policy in [ErrorPolicy::Strict, ErrorPolicy::Lenient, ErrorPolicy::Skip] {
+            let serial = sum_calibrations(&matcher, input, mode, policy);
+            for threads in 1..=8 {
+                let parallel = sum_calibrations_parallel(&matcher, input, mode, policy, threads);
+                assert_eq!(parallel, serial, "{policy:?} with {threads} threads");
+            }
+        }
+    }
+}
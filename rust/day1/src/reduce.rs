@@ -0,0 +1,137 @@
+use crate::calibration::CalibrationError;
+
+/// How to turn the digits found in a line into its calibration value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Reducer {
+    /// The first and last digit written one after the other, the puzzle's own rule
+    #[default]
+    FirstLast,
+    /// The sum of all digits
+    Sum,
+    /// All digits written one after the other
+    Concat,
+    /// The largest digit
+    Max,
+    /// The smallest digit
+    Min,
+    /// The first k digits written one after the other, or all of them if there are fewer
+    FirstK(usize),
+}
+
+impl Reducer {
+    /// Reduce the values of the digits of a line, in the order they appear in it
+    pub fn reduce<I: IntoIterator<Item = u32>>(self, values: I) -> Result<u32, CalibrationError> {
+        let mut values = values.into_iter().peekable();
+        if values.peek().is_none() {
+            return Err(CalibrationError::NoDigit);
+        }
+        let overflow = CalibrationError::Overflow;
+        match self {
+            Reducer::FirstLast => {
+                let first = values.next().ok_or(CalibrationError::NoDigit)?;
+                let last = values.last().unwrap_or(first);
+                concat(first, last).ok_or(overflow)
+            }
+            Reducer::Sum => values
+                .try_fold(0u32, |sum, val| sum.checked_add(val))
+                .ok_or(overflow),
+            Reducer::Concat => concat_all(values).ok_or(overflow),
+            Reducer::Max => values.max().ok_or(CalibrationError::NoDigit),
+            Reducer::Min => values.min().ok_or(CalibrationError::NoDigit),
+            Reducer::FirstK(k) => concat_all(values.take(k)).ok_or(overflow),
+        }
+    }
+}
+
+impl std::str::FromStr for Reducer {
+    type Err = String;
+
+    /// Parse a reducer as given on the command line: first-last, sum, concat, max, min or first:K
+    fn from_str(reducer: &str) -> Result<Reducer, String> {
+        match reducer {
+            "first-last" => Ok(Reducer::FirstLast),
+            "sum" => Ok(Reducer::Sum),
+            "concat" => Ok(Reducer::Concat),
+            "max" => Ok(Reducer::Max),
+            "min" => Ok(Reducer::Min),
+            _ => match reducer.strip_prefix("first:").map(str::parse) {
+                Some(Ok(k)) if k > 0 => Ok(Reducer::FirstK(k)),
+                _ => Err(format!(
+                    "invalid reducer {reducer:?}, expected first-last, sum, concat, max, min or \
+                     first:K"
+                )),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Reducer {
+    /// Write the reducer the way it is given on the command line
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Reducer::FirstLast => write!(f, "first-last"),
+            Reducer::Sum => write!(f, "sum"),
+            Reducer::Concat => write!(f, "concat"),
+            Reducer::Max => write!(f, "max"),
+            Reducer::Min => write!(f, "min"),
+            Reducer::FirstK(k) => write!(f, "first:{k}"),
+        }
+    }
+}
+
+// a and b written one after the other, like 23 and 7 giving 237, or None if that doesn't fit a
+// u32
+fn concat(a: u32, b: u32) -> Option<u32> {
+    let shift = 10u32.checked_pow(b.checked_ilog10().unwrap_or(0) + 1)?;
+    a.checked_mul(shift)?.checked_add(b)
+}
+
+fn concat_all<I: Iterator<Item = u32>>(mut values: I) -> Option<u32> {
+    let first = values.next()?;
+    values.try_fold(first, concat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduce() {
+        let digits = [2, 1, 9, 4];
+        let reduce = |reducer: Reducer| reducer.reduce(digits);
+        assert_eq!(reduce(Reducer::FirstLast), Ok(24));
+        assert_eq!(reduce(Reducer::Sum), Ok(16));
+        assert_eq!(reduce(Reducer::Concat), Ok(2194));
+        assert_eq!(reduce(Reducer::Max), Ok(9));
+        assert_eq!(reduce(Reducer::Min), Ok(1));
+        assert_eq!(reduce(Reducer::FirstK(3)), Ok(219));
+        assert_eq!(reduce(Reducer::FirstK(10)), Ok(2194));
+        assert_eq!(Reducer::FirstLast.reduce([7]), Ok(77));
+        assert_eq!(Reducer::FirstLast.reduce([23, 7]), Ok(237));
+        assert_eq!(Reducer::Concat.reduce([1, 0, 0]), Ok(100));
+        assert_eq!(
+            Reducer::Sum.reduce(std::iter::empty()),
+            Err(CalibrationError::NoDigit)
+        );
+        assert_eq!(
+            Reducer::Concat.reduce([9; 11]),
+            Err(CalibrationError::Overflow)
+        );
+        assert_eq!(concat(0, 5), Some(5));
+        assert_eq!(concat(429_496_729, 5), Some(u32::MAX));
+        assert_eq!(concat(429_496_729, 6), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("first-last".parse(), Ok(Reducer::FirstLast));
+        assert_eq!("max".parse(), Ok(Reducer::Max));
+        assert_eq!("first:3".parse(), Ok(Reducer::FirstK(3)));
+        assert!("first:0".parse::<Reducer>().is_err());
+        assert!("first:".parse::<Reducer>().is_err());
+        assert!("last".parse::<Reducer>().is_err());
+        for reducer in [Reducer::FirstLast, Reducer::Concat, Reducer::FirstK(12)] {
+            assert_eq!(reducer.to_string().parse(), Ok(reducer));
+        }
+    }
+}
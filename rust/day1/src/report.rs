@@ -0,0 +1,191 @@
+use std::io::{self, Write};
+
+use crate::automaton::Match;
+use crate::calibration::{Calibration, CalibrationError, Summary};
+use crate::explain::Explanation;
+
+/// How to print the results of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A log line per input line followed by the sum
+    Text,
+    /// A JSON object per input line followed by a summary object, one per output line
+    Json,
+    /// A header followed by a row per input line and a summary row
+    Csv,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Format, String> {
+        match format {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "invalid format {format:?}, expected text, json or csv"
+            )),
+        }
+    }
+}
+
+/// Everything reported about a single input line
+pub struct LineRecord<'a> {
+    pub line_no: usize,
+    pub text: &'a str,
+    pub result: &'a Result<Calibration, CalibrationError>,
+    /// What the line added to the sum, `None` if it was skipped
+    pub value: Option<u32>,
+    /// The running sum up to and including this line
+    pub sum: u64,
+}
+
+/// The header row of the CSV format
+pub const CSV_HEADER: &str =
+    "kind,line,text,first_digit,first_start,first_len,last_digit,last_start,last_len,value,sum,error";
+
+/// Writes the per-line records and the final summary of a run in one of the output formats
+pub struct Report<W> {
+    format: Format,
+    out: W,
+}
+
+impl<W: Write> Report<W> {
+    /// Start a report, writing the CSV header if there is one
+    pub fn new(format: Format, mut out: W) -> io::Result<Report<W>> {
+        if format == Format::Csv {
+            writeln!(out, "{CSV_HEADER}")?;
+        }
+        Ok(Report { format, out })
+    }
+
+    /// Write how a line was decoded, in place of its record in the text format
+    pub fn explain(
+        &mut self,
+        line_no: usize,
+        explanation: &Explanation,
+        colour: bool,
+    ) -> io::Result<()> {
+        writeln!(self.out, "line {line_no}:")?;
+        for line in explanation.render(colour).lines() {
+            writeln!(self.out, "{}", format!("  {line}").trim_end())?;
+        }
+        Ok(())
+    }
+
+    pub fn line(&mut self, record: &LineRecord) -> io::Result<()> {
+        let LineRecord {
+            line_no,
+            text,
+            result,
+            value,
+            sum,
+        } = *record;
+        match self.format {
+            // A line that failed says why, so that a line counted as 0 can't be taken for one
+            // that calibrated to 0
+            Format::Text => match (value, result) {
+                (Some(value), Ok(_)) => writeln!(
+                    self.out,
+                    "checking line {line_no}: {text} total={value} sum={sum}"
+                ),
+                (Some(value), Err(err)) => writeln!(
+                    self.out,
+                    "checking line {line_no}: {text} total={value} sum={sum} error={err}"
+                ),
+                (None, _) => Ok(()),
+            },
+            Format::Json => {
+                let (first, last, error) = match result {
+                    Ok(calibration) => (
+                        json_match(&calibration.first),
+                        json_match(&calibration.last),
+                        String::from("null"),
+                    ),
+                    Err(err) => (
+                        String::from("null"),
+                        String::from("null"),
+                        json_string(&err.to_string()),
+                    ),
+                };
+                let value = value.map_or(String::from("null"), |value| value.to_string());
+                let text = json_string(text);
+                writeln!(
+                    self.out,
+                    "{{\"kind\":\"line\",\"line\":{line_no},\"text\":{text},\"first\":{first},\
+                     \"last\":{last},\"value\":{value},\"sum\":{sum},\"error\":{error}}}"
+                )
+            }
+            Format::Csv => {
+                let (first, last, error) = match result {
+                    Ok(calibration) => (
+                        csv_match(&calibration.first),
+                        csv_match(&calibration.last),
+                        String::new(),
+                    ),
+                    Err(err) => (
+                        String::from(",,"),
+                        String::from(",,"),
+                        csv_field(&err.to_string()),
+                    ),
+                };
+                let value = value.map_or(String::new(), |value| value.to_string());
+                let text = csv_field(text);
+                writeln!(
+                    self.out,
+                    "line,{line_no},{text},{first},{last},{value},{sum},{error}"
+                )
+            }
+        }
+    }
+
+    pub fn summary(&mut self, summary: &Summary) -> io::Result<()> {
+        let Summary { lines, sum, errors } = *summary;
+        match self.format {
+            Format::Text => writeln!(self.out, "{sum}"),
+            Format::Json => writeln!(
+                self.out,
+                "{{\"kind\":\"summary\",\"lines\":{lines},\"sum\":{sum},\"errors\":{errors}}}"
+            ),
+            Format::Csv => writeln!(self.out, "summary,{lines},,,,,,,,,{sum},{errors}"),
+        }
+    }
+}
+
+fn json_match(m: &Match<u32>) -> String {
+    format!(
+        "{{\"digit\":{},\"start\":{},\"len\":{}}}",
+        m.value, m.start, m.len
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn csv_match(m: &Match<u32>) -> String {
+    format!("{},{},{}", m.value, m.start, m.len)
+}
+
+// Quote a CSV field if it contains anything that would otherwise break up the row
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
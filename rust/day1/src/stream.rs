@@ -0,0 +1,139 @@
+use std::io::{self, BufRead};
+
+use crate::calibration::{
+    locate_digits_bytes, CalibrationMode, DigitMatcher, ErrorPolicy, LineError, Summary,
+};
+
+/// Reads lines one at a time from any [`BufRead`], reusing a single buffer so that memory use is
+/// bounded by the longest line rather than the size of the input. Lines are split the same way
+/// as by [`lines`](crate::lines)
+pub struct LineReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    line_no: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> LineReader<R> {
+        LineReader {
+            reader,
+            buf: Vec::new(),
+            line_no: 0,
+        }
+    }
+
+    /// The next line and its (1-based) number, or `None` at the end of the input
+    pub fn next_line(&mut self) -> io::Result<Option<(usize, &[u8])>> {
+        self.buf.clear();
+        if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+            return Ok(None);
+        }
+        self.line_no += 1;
+
+        let line = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        Ok(Some((self.line_no, line)))
+    }
+}
+
+/// Why calibrating a stream of lines failed
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading the input failed
+    Io(io::Error),
+    /// A line could not be calibrated under [`ErrorPolicy::Strict`]
+    Line(LineError),
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "failed to read input: {err}"),
+            StreamError::Line(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> StreamError {
+        StreamError::Io(err)
+    }
+}
+
+impl From<LineError> for StreamError {
+    fn from(err: LineError) -> StreamError {
+        StreamError::Line(err)
+    }
+}
+
+/// The same as [`sum_calibrations`](crate::sum_calibrations), but reading the input line by line
+/// from a file, stdin or a pipe instead of needing all of it in memory
+pub fn sum_calibrations_reader<R: BufRead>(
+    matcher: &DigitMatcher,
+    reader: R,
+    mode: CalibrationMode,
+    policy: ErrorPolicy,
+) -> Result<Summary, StreamError> {
+    let mut lines = LineReader::new(reader);
+    let mut summary = Summary::default();
+    while let Some((line_no, line)) = lines.next_line()? {
+        let result = locate_digits_bytes(matcher, line, mode).map(|c| c.value());
+        summary.add(line_no, result, policy)?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::{lines, sum_calibrations};
+    use crate::reduce::Reducer;
+
+    #[test]
+    fn test_line_reader() {
+        let input = b"one\r\ntwo\n\nthree";
+        let mut reader = LineReader::new(&input[..]);
+        let mut read = Vec::new();
+        while let Some((line_no, line)) = reader.next_line().unwrap() {
+            read.push((line_no, line.to_vec()));
+        }
+        let split: Vec<(usize, Vec<u8>)> = (1..).zip(lines(input).map(<[u8]>::to_vec)).collect();
+        assert_eq!(read, split);
+    }
+
+    #[test]
+    fn test_reader_matches_slice() {
+        let input = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+        let matcher = DigitMatcher::default();
+        let mode = CalibrationMode::DigitsAndWords;
+        // A tiny buffer makes lines straddle the reads underneath
+        let reader = io::BufReader::with_capacity(7, &input[..]);
+        assert_eq!(
+            sum_calibrations_reader(&matcher, reader, mode, ErrorPolicy::Strict).ok(),
+            sum_calibrations(&matcher, &input, mode, ErrorPolicy::Strict).ok()
+        );
+
+        let input = b"1\nnope\n2";
+        let result = sum_calibrations_reader(&matcher, &input[..], mode, ErrorPolicy::Strict);
+        assert!(matches!(
+            result,
+            Err(StreamError::Line(LineError { line_no: 2, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_reader_sum_does_not_wrap() {
+        // Five lines worth 999999999 each add up to more than u32::MAX
+        let input = "999999999\n".repeat(5);
+        let matcher = DigitMatcher::default().with_reducer(Reducer::Concat);
+        let summary = sum_calibrations_reader(
+            &matcher,
+            input.as_bytes(),
+            CalibrationMode::Digits,
+            ErrorPolicy::Strict,
+        );
+        assert_eq!(summary.ok().map(|summary| summary.sum), Some(4_999_999_995));
+    }
+}
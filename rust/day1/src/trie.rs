@@ -0,0 +1,551 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Which of several keys starting at the same place count as a match, when one key is a prefix
+/// of another like "seven" and "seventeen"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchPolicy {
+    /// Only the shortest key, so "seventeen" reads as "seven"
+    #[default]
+    Shortest,
+    /// Only the longest key, so "seventeen" reads as "seventeen"
+    Longest,
+    /// Every key, shortest first
+    All,
+}
+
+impl std::str::FromStr for MatchPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<MatchPolicy, String> {
+        match policy {
+            "shortest" => Ok(MatchPolicy::Shortest),
+            "longest" => Ok(MatchPolicy::Longest),
+            "all" => Ok(MatchPolicy::All),
+            _ => Err(format!(
+                "invalid match policy {policy:?}, expected shortest, longest or all"
+            )),
+        }
+    }
+}
+
+/// A trie keyed by sequences of symbols of type `K` (e.g. the chars of a word) storing a value of
+/// type `V` at the end of each inserted key
+#[derive(Debug)]
+pub struct Trie<K, V> {
+    pub(crate) next: HashMap<K, Trie<K, V>>,
+    pub(crate) val: Option<V>,
+    // The number of keys stored at or below this node. Only the root can have none, as removing
+    // the last key below any other node removes the node itself
+    len: usize,
+}
+
+impl<K: Eq + Hash, V: Clone> Trie<K, V> {
+    /// An empty trie
+    pub fn new() -> Trie<K, V> {
+        Trie {
+            next: HashMap::new(),
+            val: None,
+            len: 0,
+        }
+    }
+
+    /// Store `val` under `key`, giving back the value the key had before if any
+    pub fn insert<I: IntoIterator<Item = K>>(&mut self, key: I, val: V) -> Option<V> {
+        let mut key = key.into_iter();
+        let old = match key.next() {
+            // There is more to insert, take the first symbol as the key and insert the rest of the
+            // sequence recursively as new trie nodes. If we have this key already the rest goes to
+            // the corresponding node, otherwise we create a new node as a branch of our own node
+            Some(sym) => self.next.entry(sym).or_default().insert(key, val),
+            // We reached the end of the key sequence and now we can insert our value
+            None => self.val.replace(val),
+        };
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Remove `key` and give back its value, or `None` if it wasn't stored. Nodes left without
+    /// any key below them are removed as well, so the trie is shaped as if the key had never been
+    /// inserted
+    pub fn remove<I: IntoIterator<Item = K>>(&mut self, key: I) -> Option<V> {
+        let mut key = key.into_iter();
+        let removed = match key.next() {
+            Some(sym) => {
+                let child = self.next.get_mut(&sym)?;
+                let removed = child.remove(key);
+                if child.is_empty() {
+                    self.next.remove(&sym);
+                }
+                removed
+            }
+            None => self.val.take(),
+        };
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// The value stored under exactly `key`
+    pub fn get<I: IntoIterator<Item = K>>(&self, key: I) -> Option<&V> {
+        let mut node = self;
+        for sym in key {
+            node = node.next.get(&sym)?;
+        }
+        node.val.as_ref()
+    }
+
+    /// Whether a value is stored under exactly `key`
+    pub fn contains_key<I: IntoIterator<Item = K>>(&self, key: I) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// The number of keys stored
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a value if the next symbols spell one of the keys, otherwise `None`. Also returns
+    /// the number of symbols read. The shortest key wins if several of them match
+    pub fn get_digit<I: Iterator<Item = K>>(
+        &self,
+        syms: &mut I,
+        read_count: u32,
+    ) -> (Option<V>, u32) {
+        match &self.val {
+            Some(val) => (Some(val.clone()), read_count),
+            None => match syms.next() {
+                Some(sym) => self
+                    .next
+                    .get(&sym)
+                    .map_or_else(|| (None, 0), |child| child.get_digit(syms, read_count + 1)),
+                None => (None, 0),
+            },
+        }
+    }
+
+    /// Return the values of the keys that the next symbols start with, chosen according to
+    /// `policy`, each with the number of symbols its key is long. With
+    /// [`MatchPolicy::Shortest`] this finds the same key as [`Trie::get_digit`]
+    pub fn get_matches<I: Iterator<Item = K>>(
+        &self,
+        syms: &mut I,
+        policy: MatchPolicy,
+    ) -> Vec<(V, u32)> {
+        let mut found = Vec::new();
+        let mut node = self;
+        let mut read_count = 0;
+        loop {
+            if let Some(val) = &node.val {
+                found.push((val.clone(), read_count));
+                if policy == MatchPolicy::Shortest {
+                    break;
+                }
+            }
+            match syms.next().and_then(|sym| node.next.get(&sym)) {
+                Some(child) => node = child,
+                None => break,
+            }
+            read_count += 1;
+        }
+        if policy == MatchPolicy::Longest {
+            found.drain(..found.len().saturating_sub(1));
+        }
+        found
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Trie<K, V> {
+    /// Build a trie holding the same values under each key spelled backwards, for matching while
+    /// reading a sequence from its end
+    pub fn reversed(&self) -> Trie<K, V> {
+        let mut reversed = Trie::new();
+        self.for_each_key(&mut Vec::new(), &mut |key, val| {
+            reversed.insert(key.iter().rev().cloned(), val.clone());
+        });
+        reversed
+    }
+
+    // Call f with every key stored below this node, prefixed by the symbols in path
+    pub(crate) fn for_each_key<F: FnMut(&[K], &V)>(&self, path: &mut Vec<K>, f: &mut F) {
+        if let Some(val) = &self.val {
+            f(path, val);
+        }
+        for (sym, child) in &self.next {
+            path.push(sym.clone());
+            child.for_each_key(path, f);
+            path.pop();
+        }
+    }
+}
+
+impl<K: Eq + Hash + Ord + Clone, V> Trie<K, V> {
+    /// Iterate over every key and its value, in lexicographic order of the keys
+    pub fn iter(&self) -> TrieIter<'_, K, V> {
+        TrieIter {
+            stack: vec![(Vec::new(), self)],
+        }
+    }
+
+    /// Iterate over the keys starting with `prefix`, including `prefix` itself if it is stored,
+    /// in lexicographic order
+    pub fn keys_with_prefix<I: IntoIterator<Item = K>>(
+        &self,
+        prefix: I,
+    ) -> impl Iterator<Item = Vec<K>> + '_ {
+        let mut key = Vec::new();
+        let mut node = Some(self);
+        for sym in prefix {
+            node = node.and_then(|node| node.next.get(&sym));
+            key.push(sym);
+        }
+        let stack = node.map(|node| (key, node)).into_iter().collect();
+        TrieIter { stack }.map(|(key, _)| key)
+    }
+
+    /// The longest stored key that `text` starts with, together with its value
+    pub fn longest_prefix_of<I: IntoIterator<Item = K>>(&self, text: I) -> Option<(Vec<K>, &V)> {
+        let mut key = Vec::new();
+        let mut node = self;
+        let mut longest = node.val.as_ref().map(|val| (0, val));
+        for sym in text {
+            match node.next.get(&sym) {
+                Some(child) => node = child,
+                None => break,
+            }
+            key.push(sym);
+            if let Some(val) = &node.val {
+                longest = Some((key.len(), val));
+            }
+        }
+        longest.map(|(len, val)| {
+            key.truncate(len);
+            (key, val)
+        })
+    }
+
+    /// Whether any key occurs inside another key, like "seven" or "teen" in "seventeen". Only
+    /// then can one match found in a sequence lie within another
+    pub fn has_nested_keys(&self) -> bool {
+        self.iter().any(|(key, _)| {
+            (0..key.len()).any(|start| {
+                // Walk the rest of the key from the root, looking for another key along the way
+                let mut node = self;
+                for (end, sym) in key.iter().enumerate().skip(start) {
+                    match node.next.get(sym) {
+                        Some(child) => node = child,
+                        None => return false,
+                    }
+                    if node.val.is_some() && (start > 0 || end + 1 < key.len()) {
+                        return true;
+                    }
+                }
+                false
+            })
+        })
+    }
+}
+
+/// An iterator over the keys and values of a [`Trie`] in lexicographic order, see [`Trie::iter`]
+pub struct TrieIter<'a, K, V> {
+    // The nodes still to visit with the keys leading to them, the next one to visit on top
+    stack: Vec<(Vec<K>, &'a Trie<K, V>)>,
+}
+
+impl<'a, K: Eq + Hash + Ord + Clone, V> Iterator for TrieIter<'a, K, V> {
+    type Item = (Vec<K>, &'a V);
+
+    fn next(&mut self) -> Option<(Vec<K>, &'a V)> {
+        loop {
+            let (key, node) = self.stack.pop()?;
+            // A key comes before any longer key it is a prefix of, so the node's own value is
+            // reported before its children, which go on the stack smallest symbol last
+            let mut children: Vec<_> = node.next.iter().collect();
+            children.sort_unstable_by(|a, b| b.0.cmp(a.0));
+            for (sym, child) in children {
+                let mut child_key = key.clone();
+                child_key.push(sym.clone());
+                self.stack.push((child_key, child));
+            }
+            if let Some(val) = &node.val {
+                return Some((key, val));
+            }
+        }
+    }
+}
+
+impl<V: Clone> Trie<char, V> {
+    /// Build a trie holding the same values under the UTF-8 encoding of each key, for matching
+    /// raw bytes without decoding them into chars first
+    pub fn to_bytes(&self) -> Trie<u8, V> {
+        let mut bytes = Trie::new();
+        self.for_each_key(&mut Vec::new(), &mut |key, val| {
+            let key: String = key.iter().collect();
+            bytes.insert(key.bytes(), val.clone());
+        });
+        bytes
+    }
+}
+
+impl Trie<char, u32> {
+    /// A convenience method for constructing a trie with the digits from one through nine
+    /// included, both as numerals and spelled out in English
+    pub fn with_digits() -> Trie<char, u32> {
+        let mut trie = Trie::new();
+        trie.insert("one".chars(), 1);
+        trie.insert("1".chars(), 1);
+        trie.insert("two".chars(), 2);
+        trie.insert("2".chars(), 2);
+        trie.insert("three".chars(), 3);
+        trie.insert("3".chars(), 3);
+        trie.insert("four".chars(), 4);
+        trie.insert("4".chars(), 4);
+        trie.insert("five".chars(), 5);
+        trie.insert("5".chars(), 5);
+        trie.insert("six".chars(), 6);
+        trie.insert("6".chars(), 6);
+        trie.insert("seven".chars(), 7);
+        trie.insert("7".chars(), 7);
+        trie.insert("eight".chars(), 8);
+        trie.insert("8".chars(), 8);
+        trie.insert("nine".chars(), 9);
+        trie.insert("9".chars(), 9);
+
+        trie
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> Default for Trie<K, V> {
+    fn default() -> Trie<K, V> {
+        Trie::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trie() {
+        let mut trie = Trie::new();
+        assert_eq!(trie.get_digit(&mut "".chars(), 0), (None, 0));
+
+        trie.insert("seven".chars(), 7);
+        trie.insert("nine".chars(), 9);
+
+        assert_eq!(trie.get_digit(&mut "seven".chars(), 0), (Some(7), 5));
+
+        trie.insert("7".chars(), 7);
+        assert_eq!(trie.get_digit(&mut "7".chars(), 0), (Some(7), 1));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut trie = Trie::with_digits();
+        assert_eq!(trie.len(), 18);
+        assert_eq!(trie.get("seven".chars()), Some(&7));
+        assert_eq!(trie.get("seve".chars()), None);
+        assert_eq!(trie.insert("seven".chars(), 70), Some(7));
+        assert_eq!(trie.len(), 18);
+
+        assert_eq!(trie.remove("seve".chars()), None);
+        assert_eq!(trie.remove("sevens".chars()), None);
+        assert_eq!(trie.remove("seven".chars()), Some(70));
+        assert_eq!(trie.remove("seven".chars()), None);
+        assert_eq!(trie.len(), 17);
+        assert!(!trie.contains_key("seven".chars()));
+        // "six" shares the "s" with "seven", but nothing else
+        assert!(trie.contains_key("six".chars()));
+        assert_eq!(trie.next[&'s'].next.len(), 1);
+
+        trie.remove("six".chars());
+        assert!(!trie.next.contains_key(&'s'));
+
+        let mut trie = Trie::new();
+        trie.insert("".chars(), 0);
+        trie.insert("ab".chars(), 2);
+        assert_eq!(trie.remove("".chars()), Some(0));
+        assert_eq!(trie.remove("ab".chars()), Some(2));
+        assert!(trie.is_empty());
+        assert!(trie.next.is_empty());
+    }
+
+    // A small xorshift generator, so the property test below is repeatable without a dependency
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        // A short key over a small alphabet, so that keys often share prefixes or are equal
+        fn key(&mut self) -> Vec<char> {
+            (0..self.below(5))
+                .map(|_| ['a', 'b', 'c'][self.below(3) as usize])
+                .collect()
+        }
+    }
+
+    // The number of nodes below a trie, which for a trie without empty branches is the number of
+    // distinct non-empty prefixes of its keys
+    fn count_nodes<K, V>(trie: &Trie<K, V>) -> usize {
+        trie.next.values().map(|child| 1 + count_nodes(child)).sum()
+    }
+
+    // Apply random inserts and removals to a trie and a HashMap side by side and check that they
+    // always agree
+    #[test]
+    fn test_against_hash_map() {
+        for seed in 1..=50 {
+            let mut rng = Rng(seed);
+            let mut trie = Trie::new();
+            let mut model: HashMap<Vec<char>, u64> = HashMap::new();
+            for _ in 0..200 {
+                let key = rng.key();
+                if rng.below(3) == 0 {
+                    assert_eq!(trie.remove(key.clone()), model.remove(&key), "{key:?}");
+                } else {
+                    let val = rng.below(100);
+                    assert_eq!(trie.insert(key.clone(), val), model.insert(key, val));
+                }
+
+                assert_eq!(trie.len(), model.len());
+                let key = rng.key();
+                assert_eq!(trie.get(key.clone()), model.get(&key));
+                assert_eq!(trie.contains_key(key.clone()), model.contains_key(&key));
+                for (key, val) in &model {
+                    assert_eq!(trie.get(key.iter().copied()), Some(val));
+                }
+                let prefixes: std::collections::HashSet<&[char]> = model
+                    .keys()
+                    .flat_map(|key| (1..=key.len()).map(|end| &key[..end]))
+                    .collect();
+                assert_eq!(count_nodes(&trie), prefixes.len());
+                let mut entries: Vec<(Vec<char>, &u64)> =
+                    model.iter().map(|(key, val)| (key.clone(), val)).collect();
+                entries.sort();
+                assert_eq!(trie.iter().collect::<Vec<_>>(), entries);
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut trie = Trie::new();
+        for (key, val) in [
+            ("ten", 10),
+            ("t", 0),
+            ("three", 3),
+            ("", 99),
+            ("two", 2),
+            ("tea", 1),
+        ] {
+            trie.insert(key.chars(), val);
+        }
+        let entries: Vec<(String, u32)> = trie
+            .iter()
+            .map(|(key, &val)| (key.into_iter().collect(), val))
+            .collect();
+        let mut expected = entries.clone();
+        expected.sort();
+        assert_eq!(entries, expected);
+        assert_eq!(entries.len(), trie.len());
+        assert_eq!(entries[0], (String::new(), 99));
+        assert_eq!(entries[2], (String::from("tea"), 1));
+
+        let keys = |prefix: &str| -> Vec<String> {
+            trie.keys_with_prefix(prefix.chars())
+                .map(|key| key.into_iter().collect())
+                .collect()
+        };
+        assert_eq!(keys("te"), ["tea", "ten"]);
+        assert_eq!(keys("three"), ["three"]);
+        assert_eq!(keys("tw"), ["two"]);
+        assert!(keys("x").is_empty());
+        assert!(keys("threes").is_empty());
+        assert_eq!(keys("").len(), 6);
+
+        let longest = |text: &str| {
+            trie.longest_prefix_of(text.chars())
+                .map(|(key, &val)| (key.into_iter().collect::<String>(), val))
+        };
+        assert_eq!(longest("tenth"), Some((String::from("ten"), 10)));
+        assert_eq!(longest("thr"), Some((String::from("t"), 0)));
+        assert_eq!(longest("x"), Some((String::new(), 99)));
+        trie.remove("".chars());
+        assert_eq!(trie.longest_prefix_of("x".chars()), None);
+        assert_eq!(
+            Trie::with_digits().longest_prefix_of("sevenine".chars()),
+            Some(("seven".chars().collect(), &7))
+        );
+    }
+
+    #[test]
+    fn test_get_matches() {
+        let mut trie = Trie::new();
+        trie.insert("seven".chars(), 7);
+        trie.insert("seventeen".chars(), 17);
+        trie.insert("teen".chars(), 10);
+        let get = |text: &str, policy| trie.get_matches(&mut text.chars(), policy);
+        assert_eq!(get("seventeenth", MatchPolicy::Shortest), [(7, 5)]);
+        assert_eq!(get("seventeenth", MatchPolicy::Longest), [(17, 9)]);
+        assert_eq!(get("seventeenth", MatchPolicy::All), [(7, 5), (17, 9)]);
+        assert_eq!(get("seventy", MatchPolicy::Longest), [(7, 5)]);
+        assert_eq!(get("seve", MatchPolicy::All), []);
+        assert_eq!(trie.get_digit(&mut "seventeen".chars(), 0), (Some(7), 5));
+
+        assert!(trie.has_nested_keys());
+        trie.remove("seven".chars());
+        // "teen" is still inside "seventeen"
+        assert!(trie.has_nested_keys());
+        trie.remove("teen".chars());
+        assert!(!trie.has_nested_keys());
+        assert!(!Trie::with_digits().has_nested_keys());
+    }
+
+    #[test]
+    fn test_trie_generic() {
+        let mut trie: Trie<&str, String> = Trie::new();
+        trie.insert(["twenty", "three"], String::from("23"));
+        trie.insert(["twenty"], String::from("20"));
+
+        let mut words = "twenty three".split(' ');
+        assert_eq!(trie.get_digit(&mut words, 0), (Some(String::from("20")), 1));
+
+        let mut trie: Trie<u8, char> = Trie::new();
+        trie.insert(*b"ab", 'x');
+        assert_eq!(
+            trie.get_digit(&mut b"abc".iter().copied(), 0),
+            (Some('x'), 2)
+        );
+        assert_eq!(trie.get_digit(&mut b"ba".iter().copied(), 0), (None, 0));
+    }
+
+    #[test]
+    fn test_reversed() {
+        let reversed = Trie::with_digits().reversed();
+        assert_eq!(reversed.get_digit(&mut "owt".chars(), 0), (Some(2), 3));
+        assert_eq!(reversed.get_digit(&mut "two".chars(), 0), (None, 0));
+        assert_eq!(reversed.get_digit(&mut "7".chars(), 0), (Some(7), 1));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let mut trie = Trie::new();
+        trie.insert("drei".chars(), 3);
+        trie.insert("fünf".chars(), 5);
+        let bytes = trie.to_bytes();
+        assert_eq!(bytes.get_digit(&mut "fünf".bytes(), 0), (Some(5), 5));
+        assert_eq!(bytes.get_digit(&mut "drei".bytes(), 0), (Some(3), 4));
+    }
+}
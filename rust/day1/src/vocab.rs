@@ -0,0 +1,194 @@
+use crate::trie::Trie;
+
+/// Words spelling out digits, to be recognised alongside the numerals `1` to `9`, and `0` if
+/// the vocabulary has a word for zero
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vocabulary {
+    pub words: Vec<(String, u32)>,
+}
+
+/// A line of a vocabulary file that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VocabularyError {
+    pub line_no: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for VocabularyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line_no, self.message)
+    }
+}
+
+impl std::error::Error for VocabularyError {}
+
+// The built-in vocabularies, by name, with the words for zero through nine
+const PRESETS: &[(&str, [&str; 10])] = &[
+    (
+        "en",
+        [
+            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ],
+    ),
+    (
+        "de",
+        [
+            "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+        ],
+    ),
+    (
+        "es",
+        [
+            "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+        ],
+    ),
+];
+
+impl Vocabulary {
+    /// The names of the built-in vocabularies
+    pub fn preset_names() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|&(name, _)| name)
+    }
+
+    /// One of the built-in vocabularies: English (`en`), German (`de`), French (`fr`) or Spanish
+    /// (`es`)
+    pub fn preset(name: &str) -> Option<Vocabulary> {
+        let mut vocab = Vocabulary::preset_with_zero(name)?;
+        vocab.words.retain(|&(_, val)| val != 0);
+        Some(vocab)
+    }
+
+    /// One of the built-in vocabularies including its word for zero, so that zero counts as a
+    /// digit as well
+    pub fn preset_with_zero(name: &str) -> Option<Vocabulary> {
+        let (_, words) = PRESETS.iter().find(|&&(preset, _)| preset == name)?;
+        Some(Vocabulary {
+            words: (0..)
+                .zip(words)
+                .map(|(val, &word)| (String::from(word), val))
+                .collect(),
+        })
+    }
+
+    /// Parse a vocabulary file, made of lines like `one = 1`. Blank lines and lines starting
+    /// with `#` are ignored
+    pub fn parse(text: &str) -> Result<Vocabulary, VocabularyError> {
+        let mut words = Vec::new();
+        for (line_no, line) in (1..).zip(text.lines()) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| VocabularyError {
+                line_no,
+                message: String::from(message),
+            };
+            let (word, val) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected word = value"))?;
+            let (word, val) = (word.trim(), val.trim());
+            if word.is_empty() {
+                return Err(error("missing word"));
+            }
+            match val.parse() {
+                Ok(val @ 0..=9) => words.push((String::from(word), val)),
+                _ => return Err(error("value must be a digit from 0 to 9")),
+            }
+        }
+        Ok(Vocabulary { words })
+    }
+}
+
+impl Trie<char, u32> {
+    /// A trie with the numerals `1` to `9` and the words of a vocabulary, plus the numeral `0` if
+    /// the vocabulary has a word for zero
+    pub fn with_vocabulary(vocab: &Vocabulary) -> Trie<char, u32> {
+        let mut trie = Trie::new();
+        let has_zero = vocab.words.iter().any(|&(_, val)| val == 0);
+        for val in (if has_zero { 0 } else { 1 })..=9 {
+            trie.insert(val.to_string().chars(), val);
+        }
+        for (word, val) in &vocab.words {
+            trie.insert(word.chars(), *val);
+        }
+        trie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::{calibrate, CalibrationError, CalibrationMode, DigitMatcher};
+
+    #[test]
+    fn test_parse() {
+        let vocab = Vocabulary::parse("# Dutch\n\neen = 1\n  twee=2 \nnegen = 9\n").unwrap();
+        assert_eq!(
+            vocab.words,
+            vec![
+                (String::from("een"), 1),
+                (String::from("twee"), 2),
+                (String::from("negen"), 9)
+            ]
+        );
+
+        let error = |text| Vocabulary::parse(text).unwrap_err();
+        assert_eq!(error("een = 1\ntwee 2").line_no, 2);
+        assert_eq!(error("= 1").message, "missing word");
+        assert_eq!(
+            error("tien = 10").message,
+            "value must be a digit from 0 to 9"
+        );
+        assert_eq!(error("een = x").line_no, 1);
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(
+            Vocabulary::preset_names().collect::<Vec<_>>(),
+            vec!["en", "de", "fr", "es"]
+        );
+        assert!(Vocabulary::preset("xx").is_none());
+
+        let mode = CalibrationMode::DigitsAndWords;
+        let calibrate_in = |name, line| {
+            let trie = Trie::with_vocabulary(&Vocabulary::preset(name).unwrap());
+            calibrate(&DigitMatcher::new(&trie), line, mode)
+        };
+        assert_eq!(calibrate_in("en", "two1nine"), Ok(29));
+        assert_eq!(calibrate_in("de", "xfünfabcsieben"), Ok(57));
+        assert_eq!(calibrate_in("fr", "troisquatre5neuf"), Ok(39));
+        assert_eq!(calibrate_in("es", "ochocuatrodos"), Ok(82));
+        assert_eq!(calibrate_in("es", "7one"), Ok(77));
+        assert_eq!(calibrate_in("en", "0abc5zero"), Ok(55));
+    }
+
+    #[test]
+    fn test_zero() {
+        let mode = CalibrationMode::DigitsAndWords;
+        let calibrate_in = |name, line| {
+            let trie = Trie::with_vocabulary(&Vocabulary::preset_with_zero(name).unwrap());
+            calibrate(&DigitMatcher::new(&trie), line, mode)
+        };
+        assert_eq!(calibrate_in("en", "0abc5"), Ok(5));
+        assert_eq!(calibrate_in("en", "fivexzero"), Ok(50));
+        assert_eq!(calibrate_in("en", "zero0"), Ok(0));
+        assert_eq!(calibrate_in("de", "nullacht"), Ok(8));
+        assert_eq!(calibrate_in("fr", "zéro"), Ok(0));
+        assert_eq!(calibrate_in("es", "xyz"), Err(CalibrationError::NoDigit));
+        assert_eq!(Vocabulary::preset_with_zero("en").unwrap().words.len(), 10);
+
+        // A file only counts zero if it lists a word for it
+        let trie = Trie::with_vocabulary(&Vocabulary::parse("nul = 0").unwrap());
+        assert_eq!(calibrate(&DigitMatcher::new(&trie), "nul0", mode), Ok(0));
+        let trie = Trie::with_vocabulary(&Vocabulary::parse("een = 1").unwrap());
+        assert_eq!(calibrate(&DigitMatcher::new(&trie), "0een", mode), Ok(11));
+    }
+}
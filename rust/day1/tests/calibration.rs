@@ -0,0 +1,84 @@
+use day1::{
+    calibrate, sum_calibrations, CalibrationError, CalibrationMode, DigitMatcher, ErrorPolicy,
+    LineError, Summary, Trie,
+};
+
+const PART1_EXAMPLE: &[u8] = b"1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n";
+
+const PART2_EXAMPLE: &[u8] = b"two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen
+";
+
+#[test]
+fn test_examples() {
+    let matcher = DigitMatcher::default();
+    let sum = |input, mode| {
+        sum_calibrations(&matcher, input, mode, ErrorPolicy::Strict).map(|summary| summary.sum)
+    };
+    assert_eq!(sum(PART1_EXAMPLE, CalibrationMode::Digits), Ok(142));
+    assert_eq!(sum(PART2_EXAMPLE, CalibrationMode::DigitsAndWords), Ok(281));
+}
+
+#[test]
+fn test_input() {
+    let input = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+    let matcher = DigitMatcher::default();
+    let summary = |mode| sum_calibrations(&matcher, &input, mode, ErrorPolicy::Strict);
+    assert_eq!(
+        summary(CalibrationMode::Digits),
+        Ok(Summary {
+            lines: 1000,
+            sum: 54605,
+            errors: 0
+        })
+    );
+    assert_eq!(
+        summary(CalibrationMode::DigitsAndWords).map(|summary| summary.sum),
+        Ok(55429)
+    );
+}
+
+#[test]
+fn test_error_policies() {
+    let matcher = DigitMatcher::default();
+    let input = b"two1\nnothing\n\n3";
+    let mode = CalibrationMode::DigitsAndWords;
+    assert_eq!(
+        sum_calibrations(&matcher, input, mode, ErrorPolicy::Strict),
+        Err(LineError {
+            line_no: 2,
+            error: CalibrationError::NoDigit
+        })
+    );
+    assert_eq!(
+        sum_calibrations(&matcher, input, mode, ErrorPolicy::Lenient),
+        Ok(Summary {
+            lines: 4,
+            sum: 54,
+            errors: 2
+        })
+    );
+    assert_eq!(
+        sum_calibrations(&matcher, input, mode, ErrorPolicy::Skip).map(|summary| summary.sum),
+        Ok(54)
+    );
+}
+
+#[test]
+fn test_custom_vocabulary() {
+    let mut trie = Trie::new();
+    trie.insert("uno".chars(), 1);
+    trie.insert("dos".chars(), 2);
+    let matcher = DigitMatcher::new(&trie);
+    let mode = CalibrationMode::DigitsAndWords;
+    assert_eq!(calibrate(&matcher, "xunodosx", mode), Ok(12));
+    assert_eq!(
+        calibrate(&matcher, "one2", mode),
+        Err(CalibrationError::NoDigit)
+    );
+}